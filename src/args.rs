@@ -1,53 +1,967 @@
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf};
 
 use anyhow::{Result, bail};
-use clap::{Parser, arg, command};
+use clap::{
+    ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, error::ErrorKind,
+    parser::ValueSource,
+};
 
-/// Simple program to greet a person
+use crate::{
+    config::{Config, Profile, default_config_path},
+    discovery::ArtifactDiscovery,
+    plan_output::PlanFormat,
+    snapshot::SnapshotBuilds,
+};
+
+/// Crawl a Maven repo for its metadata, then reify an artifact db from the crawl
 #[derive(Parser, Debug, Clone)]
 #[command(version, about, long_about = None)]
-pub struct Args {
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// the TOML file with the profiles. Defaults to
+    /// `~/.config/mvn_crawler_carl/config.toml`
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// the profile in the config file to take settings from.
+    /// Options on the command line override it
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// the options from before there were subcommands
+    #[command(flatten, next_help_heading = "Options without a command (deprecated)")]
+    legacy: LegacyArgs,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// find all the `maven-metadata.xml` files in the repo
+    Crawl(CrawlCommand),
+    /// show what reify would fetch
+    Plan(PlanCommand),
+    /// estimate how much reify would fetch and how long it would take
+    Estimate(EstimateCommand),
+    /// update the artifact db from a crawl
+    Reify(ReifyCommand),
+    /// check the artifact db for damaged files and write a report
+    Audit(AuditCommand),
+    /// list the crawls in the crawl db
+    List(ListCommand),
+}
+
+/// What the run does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Crawl,
+    Plan,
+    Estimate,
+    Reify,
+    Audit,
+    List,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CrawlCommand {
     /// URL of the Maven Repo
     #[arg(short, long)]
     repo: Option<String>,
 
+    /// a URL to substitute when fetching XML, jars, etc
+    #[arg(short, long)]
+    mirror: Option<String>,
+
     /// where to put the result
     #[arg(short, long)]
-    crawl_db: PathBuf,
+    crawl_db: Option<PathBuf>,
+
+    /// the artifact db, whose metadata is reused when its checksum matches
+    #[arg(long)]
+    artifact_db: Option<PathBuf>,
+
+    /// maximum number of threads, default to 200
+    #[arg(long)]
+    max_threads: Option<usize>,
+
+    #[command(flatten)]
+    crawl: CrawlArgs,
+
+    #[command(flatten)]
+    coordinates: CoordinateArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct PlanCommand {
+    /// URL of the Maven Repo. Planning doesn't fetch anything, it's
+    /// only used for each download's `source_url` in `--format jsonl` and `csv`
+    #[arg(short, long)]
+    repo: Option<String>,
+
+    /// where the crawls are
+    #[arg(short, long)]
+    crawl_db: Option<PathBuf>,
+
+    /// the directory where the artifacts are stored
+    #[arg(long)]
+    artifact_db: Option<PathBuf>,
+
+    /// maximum number of threads, default to 200
+    #[arg(long)]
+    max_threads: Option<usize>,
+
+    /// how to print the plan
+    #[arg(long, value_enum, default_value_t = PlanFormat::Summary)]
+    format: PlanFormat,
+
+    /// also write the plan to the plan file so `reify --from-plan` can run it
+    #[arg(long, default_value_t = false, action)]
+    save_plan: bool,
+
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    versions: VersionArgs,
+
+    #[command(flatten)]
+    coordinates: CoordinateArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct EstimateCommand {
+    /// URL of the Maven Repo
+    #[arg(short, long)]
+    repo: Option<String>,
 
     /// a URL to substitute when fetching XML, jars, etc
     #[arg(short, long)]
     mirror: Option<String>,
 
-    // /// should load JARs or simply keep the metadata around
-    // #[arg(long, default_value_t = false, action)]
-    // load_jars: bool,
-    /// plan the download
+    /// where the crawls are
+    #[arg(short, long)]
+    crawl_db: Option<PathBuf>,
+
+    /// the directory where the artifacts are stored
+    #[arg(long)]
+    artifact_db: Option<PathBuf>,
+
+    /// maximum number of threads, default to 200
+    #[arg(long)]
+    max_threads: Option<usize>,
+
+    #[command(flatten)]
+    estimate: EstimateArgs,
+
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    versions: VersionArgs,
+
+    #[command(flatten)]
+    coordinates: CoordinateArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ReifyCommand {
+    /// URL of the Maven Repo
+    #[arg(short, long)]
+    repo: Option<String>,
+
+    /// a URL to substitute when fetching XML, jars, etc
+    #[arg(short, long)]
+    mirror: Option<String>,
+
+    /// where the crawls are
+    #[arg(short, long)]
+    crawl_db: Option<PathBuf>,
+
+    /// the directory where the artifacts are stored
+    #[arg(long)]
+    artifact_db: Option<PathBuf>,
+
+    /// maximum number of threads, default to 200
+    #[arg(long)]
+    max_threads: Option<usize>,
+
+    /// fetch exactly what's in the plan file written by `plan --save-plan`,
+    /// skipping what an interrupted run already did
+    #[arg(long, default_value_t = false, action)]
+    from_plan: bool,
+
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    versions: VersionArgs,
+
+    #[command(flatten)]
+    coordinates: CoordinateArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AuditCommand {
+    /// the directory where the artifacts are stored
+    #[arg(long)]
+    artifact_db: Option<PathBuf>,
+
+    /// fetch damaged and missing files again
+    #[arg(long, default_value_t = false, action)]
+    repair: bool,
+
+    /// URL of the Maven Repo, needed for `--repair`
+    #[arg(short, long)]
+    repo: Option<String>,
+
+    /// a URL to substitute when fetching XML, jars, etc
+    #[arg(short, long)]
+    mirror: Option<String>,
+
+    /// maximum number of threads, default to 200
+    #[arg(long)]
+    max_threads: Option<usize>,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    versions: VersionArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ListCommand {
+    /// where the crawls are
+    #[arg(short, long)]
+    crawl_db: Option<PathBuf>,
+}
+
+/// How a crawl runs
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CrawlArgs {
+    /// resume an interrupted crawl from the checkpoint in this crawl directory
+    #[arg(long)]
+    resume: Option<PathBuf>,
+
+    /// seconds between crawl checkpoints, default to 60
+    #[arg(long)]
+    checkpoint_secs: Option<u64>,
+
+    /// download every metadata file rather than asking the server
+    /// whether it changed since the previous crawl
+    #[arg(long, default_value_t = false, action)]
+    full_crawl: bool,
+}
+
+/// How the size of a reify is estimated
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct EstimateArgs {
+    /// only ask about this many of the planned downloads, picked at
+    /// random, and scale up. By default, ask about all of them
+    #[arg(long)]
+    sample: Option<usize>,
+
+    /// the download speed in megabits per second to assume when
+    /// estimating how long the reify takes
+    #[arg(long, default_value_t = 100.0)]
+    bandwidth_mbps: f64,
+}
+
+/// Which crawl to plan or reify from, and which of its packages
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct PlanArgs {
+    /// allow plan and reify to use a crawl that never finished
+    #[arg(long, default_value_t = false, action)]
+    allow_incomplete_crawl: bool,
+
+    /// the crawl to plan or reify from, either a crawl directory or
+    /// a date like 2025_04_13_14_43_04. Defaults to the latest crawl
+    #[arg(long)]
+    crawl: Option<String>,
+
+    /// check every package in the artifact db for missing files, not
+    /// just the ones whose metadata changed
+    #[arg(long, default_value_t = false, action)]
+    backfill: bool,
+
+    /// stop planning after more than this many crawl metadata
+    /// files can't be planned. By default, never stop
+    #[arg(long)]
+    max_errors: Option<usize>,
+
+    /// only reify packages whose metadata was updated on or after
+    /// the date, e.g., `2025-01-31` or `2025-01-31T12:00:00Z`
+    #[arg(long)]
+    updated_since: Option<String>,
+
+    /// only reify packages whose metadata was updated in the last this many days
+    #[arg(long)]
+    updated_within_days: Option<i64>,
+
+    /// only reify versions that aren't in the artifact db's metadata
+    #[arg(long, default_value_t = false, action)]
+    only_new_versions: bool,
+
+    /// the plan file to save or reify from. Defaults to
+    /// `reify_plan.jsonl` in the crawl directory
+    #[arg(long)]
+    plan_file: Option<PathBuf>,
+}
+
+/// Which files of each version to fetch
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct FileArgs {
+    /// re-probe artifacts that 404'd more than this many days ago.
+    /// By default they're never asked for again
+    #[arg(long)]
+    not_found_ttl_days: Option<i64>,
+
+    /// ignore the record of artifacts that 404'd and ask for them again
+    #[arg(long, default_value_t = false, action)]
+    recheck_not_found: bool,
+
+    /// how reify works out which files to fetch for each version
+    #[arg(long, value_enum, default_value_t = ArtifactDiscovery::Suffixes)]
+    discovery: ArtifactDiscovery,
+
+    /// JSON file with the suffixes and file name globs to fetch
+    #[arg(long)]
+    file_policy: Option<PathBuf>,
+
+    /// a suffix to guess after `{artifact}-{version}`, e.g., `.pom`.
+    /// Repeat for more. Replaces the default (and policy file) suffixes
+    #[arg(long = "suffix")]
+    suffixes: Vec<String>,
+
+    /// only fetch files whose name matches the glob, e.g., `*.pom`. Repeatable
+    #[arg(long = "include-file")]
+    include_files: Vec<String>,
+
+    /// never fetch files whose name matches the glob, e.g., `*-javadoc.jar`. Repeatable
+    #[arg(long = "exclude-file")]
+    exclude_files: Vec<String>,
+
+    /// which builds of `-SNAPSHOT` versions reify fetches
+    #[arg(long, value_enum, default_value_t = SnapshotBuilds::Latest)]
+    snapshots: SnapshotBuilds,
+}
+
+/// Which versions of each artifact to fetch
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct VersionArgs {
+    /// only reify the newest this many versions of each artifact
+    #[arg(long)]
+    latest_versions: Option<usize>,
+
+    /// don't reify snapshots or alpha, beta, milestone, and rc versions
+    #[arg(long, default_value_t = false, action)]
+    releases_only: bool,
+
+    /// only reify versions in the Maven version range, e.g., `[2.0,)`
+    #[arg(long)]
+    version_range: Option<String>,
+}
+
+/// Which groups and artifacts to crawl and reify
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct CoordinateArgs {
+    /// only crawl and reify groups matching the glob, e.g., `org.apache.*`.
+    /// Can be given more than once
+    #[arg(long = "include-group")]
+    include_groups: Vec<String>,
+
+    /// don't crawl or reify groups matching the glob. Can be given more than once
+    #[arg(long = "exclude-group")]
+    exclude_groups: Vec<String>,
+
+    /// only crawl and reify artifacts whose id matches the glob, e.g., `commons-*`.
+    /// Can be given more than once
+    #[arg(long = "include-artifact")]
+    include_artifacts: Vec<String>,
+
+    /// don't crawl or reify artifacts whose id matches the glob. Can be given more than once
+    #[arg(long = "exclude-artifact")]
+    exclude_artifacts: Vec<String>,
+}
+
+/// Everything as flags, with the mode picked by `--plan`, `--reify-artifact-db`,
+/// `--audit`, or `--list-crawls`. Deprecated in favor of the subcommands
+#[derive(clap::Args, Debug, Clone)]
+pub struct LegacyArgs {
+    /// URL of the Maven Repo
+    #[arg(short, long)]
+    repo: Option<String>,
+
+    /// where to put the result
+    #[arg(short, long)]
+    crawl_db: Option<PathBuf>,
+
+    /// a URL to substitute when fetching XML, jars, etc
+    #[arg(short, long)]
+    mirror: Option<String>,
+
+    /// plan the download. Deprecated, use `plan`
     #[arg(long, default_value_t = false, action)]
     plan: bool,
 
+    /// with `--plan`, how to print the plan
+    #[arg(long, value_enum, default_value_t = PlanFormat::Summary)]
+    format: PlanFormat,
+
+    /// with `--plan`, also write the plan to the plan file
+    #[arg(long, default_value_t = false, action)]
+    save_plan: bool,
+
     /// the directory where the artifacts are stored
     #[arg(long)]
     artifact_db: Option<PathBuf>,
 
-    /// update the artifact_db from the latest crawl
+    /// update the artifact_db from the latest crawl. Deprecated, use `reify`
     #[arg(long, default_value_t = false, action)]
     reify_artifact_db: bool,
 
+    /// with `--reify-artifact-db`, fetch exactly what's in the plan file
+    #[arg(long, default_value_t = false, action)]
+    from_plan: bool,
+
     /// maximum number of threads, default to 200
     #[arg(long)]
     max_threads: Option<usize>,
+
+    /// list the crawls in the crawl db. Deprecated, use `list`
+    #[arg(long, default_value_t = false, action)]
+    list_crawls: bool,
+
+    /// check the artifact db for damaged files and write a report. Deprecated, use `audit`
+    #[arg(long, default_value_t = false, action)]
+    audit: bool,
+
+    /// with `--audit`, fetch damaged and missing files again
+    #[arg(long, default_value_t = false, action)]
+    repair: bool,
+
+    #[command(flatten)]
+    crawl: CrawlArgs,
+
+    #[command(flatten)]
+    plan_args: PlanArgs,
+
+    #[command(flatten)]
+    files: FileArgs,
+
+    #[command(flatten)]
+    versions: VersionArgs,
+
+    #[command(flatten)]
+    coordinates: CoordinateArgs,
+}
+
+impl Cli {
+    /// parse the command line, exiting with the usage on errors
+    pub fn parse_command_line() -> Cli {
+        match Cli::try_parse_command_line(std::env::args_os()) {
+            Ok(cli) => cli,
+            Err(e) => e.exit(),
+        }
+    }
+
+    /// parse the arguments. `--config` and `--profile` can go before or after
+    /// the command, but the deprecated flags can't be mixed with one
+    pub fn try_parse_command_line(
+        args: impl IntoIterator<Item = impl Into<OsString> + Clone>,
+    ) -> Result<Cli, clap::Error> {
+        let mut command = Cli::command();
+        let matches = command.try_get_matches_from_mut(args)?;
+        if let Some((name, _)) = matches.subcommand()
+            && let Some(flag) = legacy_flags_used(&matches).first()
+        {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "the command '{}' can't be used with '{}', put the options after the command",
+                    name, flag
+                ),
+            ));
+        }
+        Cli::from_arg_matches(&matches).map_err(|e| e.format(&mut command))
+    }
+
+    /// the deprecated mode flags that were used, so they can be warned about
+    pub fn deprecated_flags(&self) -> Vec<(&'static str, &'static str)> {
+        if self.command.is_some() {
+            return vec![];
+        }
+        let legacy = &self.legacy;
+        [
+            (legacy.list_crawls, "--list-crawls", "list"),
+            (legacy.plan, "--plan", "plan"),
+            (legacy.reify_artifact_db, "--reify-artifact-db", "reify"),
+            (legacy.audit, "--audit", "audit"),
+        ]
+        .into_iter()
+        .filter(|(used, _, _)| *used)
+        .map(|(_, flag, command)| (flag, command))
+        .collect()
+    }
+}
+
+/// the options without a command that were given on the command line
+fn legacy_flags_used(matches: &ArgMatches) -> Vec<String> {
+    <LegacyArgs as clap::Args>::augment_args(clap::Command::new("legacy"))
+        .get_arguments()
+        .filter(|a| matches.value_source(a.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .map(|a| match a.get_long() {
+            Some(long) => format!("--{}", long),
+            None => a.get_id().to_string(),
+        })
+        .collect()
+}
+
+/// The settings for the run, from whichever command was given
+#[derive(Debug, Clone)]
+pub struct Args {
+    mode: Mode,
+    repo: Option<String>,
+    mirror: Option<String>,
+    crawl_db: Option<PathBuf>,
+    artifact_db: Option<PathBuf>,
+    max_threads: Option<usize>,
+    repair: bool,
+    crawl: CrawlArgs,
+    plan: PlanArgs,
+    files: FileArgs,
+    versions: VersionArgs,
+    coordinates: CoordinateArgs,
+    estimate: EstimateArgs,
+    plan_format: PlanFormat,
+    save_plan: bool,
+    from_plan: bool,
+    /// the config profile the settings were filled in from
+    profile: Option<String>,
 }
 
 impl Args {
-    /// update the artifact_db from the latest crawl
-    pub fn reify_artifact_db(&self) -> bool {
-        self.reify_artifact_db
+    fn for_mode(mode: Mode) -> Args {
+        Args {
+            mode,
+            repo: None,
+            mirror: None,
+            crawl_db: None,
+            artifact_db: None,
+            max_threads: None,
+            repair: false,
+            crawl: CrawlArgs::default(),
+            plan: PlanArgs::default(),
+            files: FileArgs::default(),
+            versions: VersionArgs::default(),
+            coordinates: CoordinateArgs::default(),
+            estimate: EstimateArgs::default(),
+            plan_format: PlanFormat::default(),
+            save_plan: false,
+            from_plan: false,
+            profile: None,
+        }
+    }
+}
+
+impl TryFrom<Cli> for Args {
+    type Error = anyhow::Error;
+
+    /// the settings from the command line, filled in from the
+    /// profile, then checked that the command has what it needs
+    fn try_from(cli: Cli) -> Result<Args> {
+        let profile = match &cli.profile {
+            Some(name) => {
+                let path = match cli.config.clone().or_else(default_config_path) {
+                    Some(v) => v,
+                    None => bail!("No `--config` given and no home directory to find one in"),
+                };
+                Some((name.clone(), Config::load(&path)?.profile(name)?.clone()))
+            }
+            None => {
+                if cli.config.is_some() {
+                    bail!("`--config` needs a `--profile` to take the settings from");
+                }
+                None
+            }
+        };
+        let mut args = Args::from_command(cli);
+        if let Some((name, profile)) = profile {
+            args.apply_profile(name, profile);
+        }
+        args.validate()?;
+        Ok(args)
+    }
+}
+
+impl Args {
+    fn from_command(cli: Cli) -> Args {
+        match cli.command {
+            Some(Command::Crawl(c)) => Args {
+                repo: c.repo,
+                mirror: c.mirror,
+                crawl_db: c.crawl_db,
+                artifact_db: c.artifact_db,
+                max_threads: c.max_threads,
+                crawl: c.crawl,
+                coordinates: c.coordinates,
+                ..Args::for_mode(Mode::Crawl)
+            },
+            Some(Command::Plan(c)) => Args {
+                repo: c.repo,
+                crawl_db: c.crawl_db,
+                artifact_db: c.artifact_db,
+                max_threads: c.max_threads,
+                plan: c.plan,
+                files: c.files,
+                versions: c.versions,
+                coordinates: c.coordinates,
+                plan_format: c.format,
+                save_plan: c.save_plan,
+                ..Args::for_mode(Mode::Plan)
+            },
+            Some(Command::Estimate(c)) => Args {
+                repo: c.repo,
+                mirror: c.mirror,
+                crawl_db: c.crawl_db,
+                artifact_db: c.artifact_db,
+                max_threads: c.max_threads,
+                estimate: c.estimate,
+                plan: c.plan,
+                files: c.files,
+                versions: c.versions,
+                coordinates: c.coordinates,
+                ..Args::for_mode(Mode::Estimate)
+            },
+            Some(Command::Reify(c)) => Args {
+                repo: c.repo,
+                mirror: c.mirror,
+                crawl_db: c.crawl_db,
+                artifact_db: c.artifact_db,
+                max_threads: c.max_threads,
+                plan: c.plan,
+                files: c.files,
+                versions: c.versions,
+                coordinates: c.coordinates,
+                from_plan: c.from_plan,
+                ..Args::for_mode(Mode::Reify)
+            },
+            Some(Command::Audit(c)) => Args {
+                repo: c.repo,
+                mirror: c.mirror,
+                artifact_db: c.artifact_db,
+                max_threads: c.max_threads,
+                repair: c.repair,
+                files: c.files,
+                versions: c.versions,
+                ..Args::for_mode(Mode::Audit)
+            },
+            Some(Command::List(c)) => Args {
+                crawl_db: c.crawl_db,
+                ..Args::for_mode(Mode::List)
+            },
+            None => {
+                let legacy = cli.legacy;
+                // the order `main` used to check the flags in
+                let mode = if legacy.list_crawls {
+                    Mode::List
+                } else if legacy.plan {
+                    Mode::Plan
+                } else if legacy.reify_artifact_db {
+                    Mode::Reify
+                } else if legacy.audit {
+                    Mode::Audit
+                } else {
+                    Mode::Crawl
+                };
+                Args {
+                    mode,
+                    repo: legacy.repo,
+                    mirror: legacy.mirror,
+                    crawl_db: legacy.crawl_db,
+                    artifact_db: legacy.artifact_db,
+                    max_threads: legacy.max_threads,
+                    repair: legacy.repair,
+                    crawl: legacy.crawl,
+                    plan: legacy.plan_args,
+                    files: legacy.files,
+                    versions: legacy.versions,
+                    coordinates: legacy.coordinates,
+                    estimate: EstimateArgs::default(),
+                    plan_format: legacy.format,
+                    save_plan: legacy.save_plan,
+                    from_plan: legacy.from_plan,
+                    profile: None,
+                }
+            }
+        }
+    }
+
+    /// fill in whatever wasn't given on the command line from the profile
+    fn apply_profile(&mut self, name: String, profile: Profile) {
+        fn or_profile<T>(arg: &mut Option<T>, from_profile: Option<T>) {
+            if arg.is_none() {
+                *arg = from_profile;
+            }
+        }
+        fn or_profile_list(arg: &mut Vec<String>, from_profile: Vec<String>) {
+            if arg.is_empty() {
+                *arg = from_profile;
+            }
+        }
+        or_profile(&mut self.repo, profile.repo);
+        or_profile(&mut self.mirror, profile.mirror);
+        or_profile(&mut self.crawl_db, profile.crawl_db);
+        or_profile(&mut self.artifact_db, profile.artifact_db);
+        or_profile(&mut self.max_threads, profile.max_threads);
+        or_profile(&mut self.files.file_policy, profile.file_policy);
+        let coordinates = &mut self.coordinates;
+        or_profile_list(&mut coordinates.include_groups, profile.include_groups);
+        or_profile_list(&mut coordinates.exclude_groups, profile.exclude_groups);
+        or_profile_list(
+            &mut coordinates.include_artifacts,
+            profile.include_artifacts,
+        );
+        or_profile_list(
+            &mut coordinates.exclude_artifacts,
+            profile.exclude_artifacts,
+        );
+        self.profile = Some(name);
+    }
+
+    /// make sure the command has the settings it needs before starting
+    fn validate(&self) -> Result<()> {
+        let needs_repo = match self.mode {
+            Mode::Crawl | Mode::Estimate | Mode::Reify => true,
+            Mode::Audit => self.repair,
+            Mode::Plan | Mode::List => false,
+        };
+        let needs_crawl_db = self.mode != Mode::Audit;
+        let needs_artifact_db = matches!(
+            self.mode,
+            Mode::Plan | Mode::Estimate | Mode::Reify | Mode::Audit
+        );
+
+        let mut missing = vec![];
+        if needs_repo && self.repo.is_none() {
+            missing.push("--repo");
+        }
+        if needs_crawl_db && self.crawl_db.is_none() {
+            missing.push("--crawl-db");
+        }
+        if needs_artifact_db && self.artifact_db.is_none() {
+            missing.push("--artifact-db");
+        }
+        if !missing.is_empty() {
+            bail!(
+                "`{}` needs {}, either on the command line or in the `--profile`",
+                format!("{:?}", self.mode).to_lowercase(),
+                missing.join(", ")
+            );
+        }
+        let bandwidth = self.estimate.bandwidth_mbps;
+        if self.mode == Mode::Estimate && (bandwidth.is_nan() || bandwidth <= 0.0) {
+            bail!("`--bandwidth-mbps` must be more than 0, got {}", bandwidth);
+        }
+        Ok(())
+    }
+}
+
+impl Args {
+    /// what the run does
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// how to print the plan
+    pub fn plan_format(&self) -> PlanFormat {
+        self.plan_format
+    }
+
+    /// write the plan to the plan file
+    pub fn save_plan(&self) -> bool {
+        self.save_plan
+    }
+
+    /// reify what's in the plan file instead of planning
+    pub fn from_plan(&self) -> bool {
+        self.from_plan
+    }
+
+    /// the plan file given with `--plan-file`
+    pub fn plan_file(&self) -> &Option<PathBuf> {
+        &self.plan.plan_file
+    }
+
+    /// how many planned downloads to estimate from, all if `None`
+    pub fn sample(&self) -> Option<usize> {
+        self.estimate.sample
+    }
+
+    /// the download speed to assume when estimating, in megabits per second
+    pub fn bandwidth_mbps(&self) -> f64 {
+        self.estimate.bandwidth_mbps
+    }
+
+    /// the config profile in use, if any
+    pub fn profile(&self) -> &Option<String> {
+        &self.profile
     }
 
     pub fn max_threads(&self) -> usize {
         self.max_threads.unwrap_or(200)
     }
+
+    /// the crawl directory to resume, if any
+    pub fn resume(&self) -> Option<PathBuf> {
+        self.crawl.resume.clone()
+    }
+
+    /// how often to checkpoint the crawl
+    pub fn checkpoint_secs(&self) -> u64 {
+        self.crawl.checkpoint_secs.unwrap_or(60)
+    }
+
+    /// use crawls that don't have a completion manifest
+    pub fn allow_incomplete_crawl(&self) -> bool {
+        self.plan.allow_incomplete_crawl
+    }
+
+    /// the crawl to plan or reify from
+    pub fn crawl(&self) -> Option<String> {
+        self.plan.crawl.clone()
+    }
+
+    /// don't reuse metadata from the previous crawl
+    pub fn full_crawl(&self) -> bool {
+        self.crawl.full_crawl
+    }
+
+    /// how long before artifacts that 404'd are asked for again
+    pub fn not_found_ttl_days(&self) -> Option<i64> {
+        self.files.not_found_ttl_days
+    }
+
+    /// ask for artifacts that 404'd
+    pub fn recheck_not_found(&self) -> bool {
+        self.files.recheck_not_found
+    }
+
+    /// how to work out which files to fetch
+    pub fn discovery(&self) -> ArtifactDiscovery {
+        self.files.discovery
+    }
+
+    /// the file with the suffix and file name policy
+    pub fn file_policy(&self) -> &Option<PathBuf> {
+        &self.files.file_policy
+    }
+
+    /// suffixes from the command line
+    pub fn suffixes(&self) -> &Vec<String> {
+        &self.files.suffixes
+    }
+
+    /// file name globs to fetch
+    pub fn include_files(&self) -> &Vec<String> {
+        &self.files.include_files
+    }
+
+    /// file name globs not to fetch
+    pub fn exclude_files(&self) -> &Vec<String> {
+        &self.files.exclude_files
+    }
+
+    /// look for missing files in unchanged packages
+    pub fn backfill(&self) -> bool {
+        self.plan.backfill
+    }
+
+    /// repair what the audit finds
+    pub fn repair(&self) -> bool {
+        self.repair
+    }
+
+    /// which snapshot builds to fetch
+    pub fn snapshots(&self) -> SnapshotBuilds {
+        self.files.snapshots
+    }
+
+    /// how many bad crawl entries before the plan stops
+    pub fn max_errors(&self) -> Option<usize> {
+        self.plan.max_errors
+    }
+
+    /// how many of the newest versions to reify
+    pub fn latest_versions(&self) -> Option<usize> {
+        self.versions.latest_versions
+    }
+
+    /// skip pre-release versions
+    pub fn releases_only(&self) -> bool {
+        self.versions.releases_only
+    }
+
+    /// the range of versions to reify
+    pub fn version_range(&self) -> &Option<String> {
+        &self.versions.version_range
+    }
+
+    /// the earliest update to reify
+    pub fn updated_since(&self) -> &Option<String> {
+        &self.plan.updated_since
+    }
+
+    /// how many days of updates to reify
+    pub fn updated_within_days(&self) -> Option<i64> {
+        self.plan.updated_within_days
+    }
+
+    /// skip versions the artifact db already lists
+    pub fn only_new_versions(&self) -> bool {
+        self.plan.only_new_versions
+    }
+
+    /// does the reify leave out packages or versions the policies
+    /// would fetch? Then it isn't a full reify under them
+    pub fn is_scoped(&self) -> bool {
+        self.plan.updated_since.is_some()
+            || self.plan.updated_within_days.is_some()
+            || self.plan.only_new_versions
+            || !self.coordinates.include_groups.is_empty()
+            || !self.coordinates.exclude_groups.is_empty()
+            || !self.coordinates.include_artifacts.is_empty()
+            || !self.coordinates.exclude_artifacts.is_empty()
+    }
+
+    /// globs of the groups to crawl and reify
+    pub fn include_groups(&self) -> &Vec<String> {
+        &self.coordinates.include_groups
+    }
+
+    /// globs of the groups to skip
+    pub fn exclude_groups(&self) -> &Vec<String> {
+        &self.coordinates.exclude_groups
+    }
+
+    /// globs of the artifact ids to crawl and reify
+    pub fn include_artifacts(&self) -> &Vec<String> {
+        &self.coordinates.include_artifacts
+    }
+
+    /// globs of the artifact ids to skip
+    pub fn exclude_artifacts(&self) -> &Vec<String> {
+        &self.coordinates.exclude_artifacts
+    }
     /// Substitute a URL when fetching an asset
     pub fn mirror_url(&self) -> &Option<String> {
         &self.mirror
@@ -57,13 +971,11 @@ impl Args {
     }
 
     /// get the destination for the crawl data
-    pub fn crawl_db(&self) -> PathBuf {
-        self.crawl_db.clone()
-    }
-
-    /// should we plan what happens on a merge?
-    pub fn plan(&self) -> bool {
-        self.plan
+    pub fn crawl_db(&self) -> Result<PathBuf> {
+        match &self.crawl_db {
+            Some(v) => Ok(v.clone()),
+            None => bail!("Crawl db directory must be specified with the `--crawl-db` parameter"),
+        }
     }
 
     pub fn artifact_db(&self) -> Result<PathBuf> {
@@ -75,3 +987,102 @@ impl Args {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_command_line(["mvn_crawler_carl"].iter().chain(args))
+    }
+
+    #[test]
+    fn profile_before_or_after_the_command() {
+        for line in [
+            &["--profile", "central", "crawl"][..],
+            &["crawl", "--profile", "central"],
+            &["--config", "c.toml", "--profile", "central", "reify"],
+            &["--config", "c.toml", "reify", "--profile", "central"],
+        ] {
+            let cli = parse(line).expect("parses");
+            assert!(cli.command.is_some(), "{:?}", line);
+            assert_eq!(cli.profile.as_deref(), Some("central"), "{:?}", line);
+        }
+        // the deprecated form still takes them
+        let cli = parse(&["--profile", "central", "--plan"]).expect("parses");
+        assert!(cli.command.is_none());
+        assert_eq!(cli.deprecated_flags(), vec![("--plan", "plan")]);
+    }
+
+    #[test]
+    fn deprecated_flags_with_a_command() {
+        for line in [
+            &["--repo", "http://localhost/", "crawl"][..],
+            &["--plan", "plan"],
+            &["--include-group", "org.*", "reify"],
+        ] {
+            let e = parse(line).expect_err("mixes the forms");
+            assert_eq!(e.kind(), ErrorKind::ArgumentConflict, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn config_without_profile() {
+        let cli = parse(&["--config", "c.toml", "list", "--crawl-db", "x"]).expect("parses");
+        assert!(Args::try_from(cli).is_err());
+    }
+
+    #[test]
+    fn bandwidth_must_be_positive() {
+        let estimate = |mbps: &str| {
+            let bandwidth = format!("--bandwidth-mbps={}", mbps);
+            let cli = parse(&[
+                "estimate",
+                "--repo",
+                "http://localhost/",
+                "--crawl-db",
+                "c",
+                "--artifact-db",
+                "a",
+                &bandwidth,
+            ])
+            .expect("parses");
+            Args::try_from(cli)
+        };
+        assert!(estimate("12.5").is_ok());
+        for mbps in ["0", "-5", "NaN"] {
+            assert!(estimate(mbps).is_err(), "{}", mbps);
+        }
+    }
+
+    #[test]
+    fn scoped_reifies() {
+        assert!(!Args::for_mode(Mode::Reify).is_scoped());
+
+        let mut args = Args::for_mode(Mode::Reify);
+        args.plan.only_new_versions = true;
+        assert!(args.is_scoped());
+
+        let mut args = Args::for_mode(Mode::Reify);
+        args.plan.updated_since = Some("2025-01-31".to_string());
+        assert!(args.is_scoped());
+
+        let mut args = Args::for_mode(Mode::Reify);
+        args.plan.updated_within_days = Some(7);
+        assert!(args.is_scoped());
+
+        let mut args = Args::for_mode(Mode::Reify);
+        args.coordinates.include_groups = vec!["org.apache.*".to_string()];
+        assert!(args.is_scoped());
+
+        let mut args = Args::for_mode(Mode::Reify);
+        args.coordinates.exclude_artifacts = vec!["*-tests".to_string()];
+        assert!(args.is_scoped());
+
+        // backfilling and version policies still cover every package
+        let mut args = Args::for_mode(Mode::Reify);
+        args.plan.backfill = true;
+        args.versions.latest_versions = Some(3);
+        assert!(!args.is_scoped());
+    }
+}
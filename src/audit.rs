@@ -0,0 +1,372 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{File, read, read_dir, remove_file},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::{
+    checksum::{ChecksumAlgorithm, hex_digests, parse_checksum},
+    discovery::{group_and_artifact, is_sidecar},
+    json_file::write_json,
+    metadata::{ArtifactMetadata, MetadataKind, metadata_kind},
+    plan_merge::{
+        MergeCmd, MergeGroup, PlanReason, end_merge_workers, file_entry, planned_paths,
+        spawn_merge_workers, version_from_metadata,
+    },
+    response_data::{GOLD_FILE, TEMP_SUFFIX},
+    run_state::{ARTIFACT_DB_STATE_DIR, State},
+    version_policy::VersionPolicy,
+};
+
+pub const AUDIT_REPORT_FILE: &str = "audit_report.json";
+
+/// the extensions of files that should open as a zip
+const ARCHIVE_EXTENSIONS: [&str; 8] = ["jar", "war", "ear", "aar", "zip", "hpi", "jpi", "nbm"];
+
+/// What's wrong with a file in the artifact db
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditProblemKind {
+    EmptyFile,
+    /// a jar (or other zip) that doesn't open, usually because it's truncated
+    BadArchive {
+        error: String,
+    },
+    ChecksumMismatch {
+        algorithm: ChecksumAlgorithm,
+        expected: String,
+        actual: String,
+    },
+    /// the checksum file next to the artifact doesn't hold a hash
+    BadChecksumFile {
+        algorithm: ChecksumAlgorithm,
+    },
+    BadMetadata {
+        error: String,
+    },
+    /// the metadata lists a version with nothing in its directory
+    VersionWithoutArtifacts {
+        group: String,
+        artifact: String,
+        version: String,
+    },
+    /// left behind by a write that was interrupted
+    TempFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditProblem {
+    /// relative to the artifact db
+    pub path: String,
+    #[serde(flatten)]
+    pub kind: AuditProblemKind,
+}
+
+/// The result of an audit, written to the artifact db's state directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub artifact_db: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub files_checked: usize,
+    pub bytes_checked: u64,
+    pub repairs_queued: usize,
+    pub problems: Vec<AuditProblem>,
+}
+
+fn is_archive(name: &str) -> bool {
+    match name.rsplit_once('.') {
+        Some((_, ext)) => ARCHIVE_EXTENSIONS.contains(&ext),
+        None => false,
+    }
+}
+
+/// does the version directory have anything other than checksum files?
+fn has_artifacts(dir: &Path) -> bool {
+    match read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).any(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            e.path().is_file() && !is_sidecar(&name) && !name.ends_with(TEMP_SUFFIX)
+        }),
+        Err(_) => false,
+    }
+}
+
+/// check that the versions the policy picks have artifacts
+fn check_metadata(bytes: &[u8], dir: &Path, policy: &VersionPolicy) -> Vec<AuditProblemKind> {
+    // group and version metadata don't list versions
+    match metadata_kind(bytes) {
+        Ok(MetadataKind::Artifact) => {}
+        Ok(_) => return vec![],
+        Err(e) => {
+            return vec![AuditProblemKind::BadMetadata {
+                error: e.to_string(),
+            }];
+        }
+    }
+    match version_from_metadata(bytes) {
+        Ok(ArtifactMetadata {
+            group_id: group,
+            artifact_id: artifact,
+            versions,
+            ..
+        }) => policy
+            .select(versions)
+            .into_iter()
+            .filter(|v| !has_artifacts(&dir.join(v)))
+            .map(|version| AuditProblemKind::VersionWithoutArtifacts {
+                group: group.clone(),
+                artifact: artifact.clone(),
+                version,
+            })
+            .collect(),
+        Err(e) => vec![AuditProblemKind::BadMetadata {
+            error: e.to_string(),
+        }],
+    }
+}
+
+/// check a file that isn't a checksum file. The file is streamed through
+/// the hashers since artifacts can be bigger than memory
+fn check_file(path: &Path, name: &str, policy: &VersionPolicy) -> Result<Vec<AuditProblemKind>> {
+    if path.metadata()?.len() == 0 {
+        return Ok(vec![AuditProblemKind::EmptyFile]);
+    }
+    let mut ret = vec![];
+    if is_archive(name)
+        && let Err(e) = ZipArchive::new(File::open(path)?)
+    {
+        ret.push(AuditProblemKind::BadArchive {
+            error: e.to_string(),
+        });
+    }
+    let mut expected = vec![];
+    for algorithm in ChecksumAlgorithm::all() {
+        let checksum_path = path.with_file_name(format!("{}{}", name, algorithm.extension()));
+        if !checksum_path.is_file() {
+            continue;
+        }
+        match parse_checksum(&read(&checksum_path)?) {
+            Some(hash) => expected.push((algorithm, hash)),
+            None => ret.push(AuditProblemKind::BadChecksumFile { algorithm }),
+        }
+    }
+    if !expected.is_empty() {
+        let algorithms: Vec<_> = expected.iter().map(|(a, _)| *a).collect();
+        let actual = hex_digests(File::open(path)?, &algorithms)?;
+        for ((algorithm, expected), actual) in expected.into_iter().zip(actual) {
+            if actual != expected {
+                ret.push(AuditProblemKind::ChecksumMismatch {
+                    algorithm,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    // metadata is small, so it's read whole to check the versions it lists
+    if name == GOLD_FILE
+        && let Some(dir) = path.parent()
+    {
+        ret.extend(check_metadata(&read(path)?, dir, policy));
+    }
+    Ok(ret)
+}
+
+/// walk the artifact db, checking every file with `max_threads` threads
+pub fn audit_artifact_db(state: State) -> Result<AuditReport> {
+    let artifact_db = state.artifact_db()?;
+    let version_policy = state.version_policy()?;
+    let started_at = Utc::now();
+    let files_checked = AtomicUsize::new(0);
+    let bytes_checked = AtomicU64::new(0);
+    let problems = Mutex::new(vec![]);
+    info!("Auditing {:?}", artifact_db);
+
+    let (tx, rx) = flume::bounded::<PathBuf>(1000);
+    thread::scope(|s| {
+        for _ in 0..state.max_threads() {
+            let rx = rx.clone();
+            let (artifact_db, files_checked, bytes_checked, problems) =
+                (&artifact_db, &files_checked, &bytes_checked, &problems);
+            s.spawn(move || {
+                for path in rx {
+                    let rel = match path.strip_prefix(artifact_db) {
+                        Ok(p) => p.to_string_lossy().to_string(),
+                        Err(_) => continue,
+                    };
+                    let name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let found = if name.ends_with(TEMP_SUFFIX) {
+                        vec![AuditProblemKind::TempFile]
+                    } else {
+                        match check_file(&path, &name, version_policy) {
+                            Ok(found) => found,
+                            Err(e) => {
+                                error!("Couldn't check {:?} {}", path, e);
+                                continue;
+                            }
+                        }
+                    };
+                    let len = path.metadata().map(|m| m.len()).unwrap_or_default();
+                    bytes_checked.fetch_add(len, Ordering::Relaxed);
+                    let cnt = files_checked.fetch_add(1, Ordering::Relaxed) + 1;
+                    if cnt % 10_000 == 0 {
+                        info!("Audited {} files", cnt.separate_with_commas());
+                    }
+                    let mut problems = problems.lock().expect("Lock audit problems");
+                    for kind in found {
+                        problems.push(AuditProblem {
+                            path: rel.clone(),
+                            kind,
+                        });
+                    }
+                }
+            });
+        }
+        drop(rx);
+
+        let walker = WalkDir::new(&artifact_db)
+            .into_iter()
+            .filter_entry(|e| !(e.depth() == 1 && e.file_name() == ARTIFACT_DB_STATE_DIR));
+        for entry in walker.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy();
+            if !entry.file_type().is_file() || is_sidecar(&name) {
+                continue;
+            }
+            if tx.send(entry.into_path()).is_err() {
+                break;
+            }
+        }
+        drop(tx);
+    });
+
+    let mut problems = problems.into_inner().expect("Unlock audit problems");
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(AuditReport {
+        artifact_db,
+        started_at,
+        finished_at: Utc::now(),
+        files_checked: files_checked.into_inner(),
+        bytes_checked: bytes_checked.into_inner(),
+        repairs_queued: 0,
+        problems,
+    })
+}
+
+/// fix what the audit found: temp files and damaged metadata are removed
+/// (so the next reify plans the package again) and everything else is
+/// fetched again by the merge workers. Returns the number of files queued
+pub fn repair(report: &AuditReport, state: State) -> Result<usize> {
+    let artifact_db = state.artifact_db()?;
+    let negative_cache = state.negative_cache()?;
+    let policy = state.file_policy()?;
+    // group the fetches by version directory
+    let mut to_fetch: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // a file can have more than one problem, so remove each once
+    let mut to_remove = BTreeSet::new();
+    for problem in &report.problems {
+        let (dir, name) = match problem.path.rsplit_once('/') {
+            Some((dir, name)) => (format!("{}/", dir), name),
+            None => (String::new(), problem.path.as_str()),
+        };
+        match &problem.kind {
+            AuditProblemKind::TempFile | AuditProblemKind::BadMetadata { .. } => {
+                to_remove.insert(problem.path.clone());
+            }
+            AuditProblemKind::VersionWithoutArtifacts {
+                group,
+                artifact,
+                version,
+            } => {
+                let paths = planned_paths(
+                    group,
+                    artifact,
+                    std::slice::from_ref(version),
+                    state.discovery(),
+                    policy,
+                );
+                let version_dir = format!("{}{}/", dir, version);
+                to_fetch.entry(version_dir).or_default().extend(paths);
+            }
+            // the metadata is the commit, so fetching today's copy would
+            // list versions that were never downloaded. Remove it instead
+            _ if name == GOLD_FILE => {
+                to_remove.insert(problem.path.clone());
+            }
+            _ => to_fetch.entry(dir).or_default().push(problem.path.clone()),
+        }
+    }
+    for path in to_remove {
+        info!("Removing {}", path);
+        remove_file(artifact_db.join(&path))?;
+    }
+
+    let mut queued = 0;
+    let tx = spawn_merge_workers(state.clone());
+    for (dir, mut paths) in to_fetch {
+        paths.sort();
+        paths.dedup();
+        let mut entries = vec![];
+        for path in paths {
+            if negative_cache.is_known_missing(&path) {
+                continue;
+            }
+            let expand = path.ends_with('/');
+            let mut entry = file_entry(path, state.clone())?;
+            entry.expand = expand;
+            entries.push(entry);
+        }
+        if entries.is_empty() {
+            continue;
+        }
+        queued += entries.len();
+        let (group, artifact) = group_and_artifact(&dir).unwrap_or_default();
+        tx.send(MergeCmd::Merge(MergeGroup::new(
+            entries,
+            &group,
+            &artifact,
+            PlanReason::Repair,
+        )))?;
+    }
+    end_merge_workers(&tx, &state)?;
+    Ok(queued)
+}
+
+/// audit the artifact db, optionally repair it, and write the report
+pub fn audit_and_report(state: State) -> Result<AuditReport> {
+    let mut report = audit_artifact_db(state.clone())?;
+    info!(
+        "Audited {} files ({} bytes), found {} problems",
+        report.files_checked.separate_with_commas(),
+        report.bytes_checked.separate_with_commas(),
+        report.problems.len().separate_with_commas()
+    );
+    if state.repair() && !report.problems.is_empty() {
+        report.repairs_queued = repair(&report, state.clone())?;
+        info!(
+            "Repaired with {} fetches, {} failed downloads",
+            report.repairs_queued.separate_with_commas(),
+            state.failed_download_cnt().separate_with_commas()
+        );
+    }
+    let report_file = state.artifact_db_state_dir()?.join(AUDIT_REPORT_FILE);
+    write_json(&report_file, &report)?;
+    info!("Wrote audit report to {:?}", report_file);
+    Ok(report)
+}
@@ -0,0 +1,87 @@
+use std::{
+    path::Path,
+    thread::{self, JoinHandle, sleep},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+
+use crate::{
+    json_file::{read_json, write_json},
+    run_state::State,
+};
+
+pub const CHECKPOINT_FILE: &str = "crawl_checkpoint.json";
+
+/// Everything needed to pick a crawl back up after
+/// the process dies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlCheckpoint {
+    pub repo_url: String,
+    pub mirror_url: Option<String>,
+    pub saved_at: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub run_millis: u64,
+    pub fetch_cnt: usize,
+    pub asset_fetch_cnt: usize,
+    pub total_added_pages: usize,
+    pub total_bytes: usize,
+    pub metadata_cnt: usize,
+    #[serde(default)]
+    pub not_modified_cnt: usize,
+    #[serde(default)]
+    pub checksum_skip_cnt: usize,
+    pub error_cnt: usize,
+    pub queue: Vec<String>,
+    pub visited: Vec<String>,
+}
+
+impl CrawlCheckpoint {
+    /// load the checkpoint from the crawl directory
+    pub fn load(crawl_dir: &Path) -> Result<CrawlCheckpoint> {
+        read_json(&crawl_dir.join(CHECKPOINT_FILE))
+    }
+
+    /// write the checkpoint to the crawl directory
+    pub fn save(&self, crawl_dir: &Path) -> Result<()> {
+        write_json(&crawl_dir.join(CHECKPOINT_FILE), self)
+    }
+}
+
+/// snapshot the state and write it to the crawl directory
+pub fn save_checkpoint(state: State) -> Result<()> {
+    let checkpoint = state.checkpoint()?;
+    checkpoint.save(&state.crawl_db_dest_dir())?;
+    info!(
+        "Checkpointed crawl, queue size {} visited {}",
+        checkpoint.queue.len().separate_with_commas(),
+        checkpoint.visited.len().separate_with_commas()
+    );
+    Ok(())
+}
+
+/// checkpoint every `--checkpoint-secs` until the crawl threads are done.
+/// Join the returned handle before the final checkpoint so the two
+/// don't write at the same time
+pub fn periodic_checkpoint(state: State) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut last = Instant::now();
+        while state.thread_cnt() > 0 {
+            // wake up often so the thread ends soon after the crawl does
+            sleep(Duration::from_millis(200));
+            if last.elapsed() < Duration::from_secs(state.checkpoint_secs())
+                || state.thread_cnt() == 0
+            {
+                continue;
+            }
+            if let Err(e) = save_checkpoint(state.clone()) {
+                error!("Failed to checkpoint crawl {:?}", e);
+            }
+            last = Instant::now();
+        }
+    })
+}
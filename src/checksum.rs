@@ -0,0 +1,105 @@
+use std::io::{self, Read};
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512, digest::DynDigest};
+
+/// the hex SHA-1 of the bytes
+pub fn sha1_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(bytes))
+}
+
+/// get the hash out of a checksum file like `maven-metadata.xml.sha1`.
+/// Some repos follow the hash with the file name, so take the first word
+pub fn parse_checksum(bytes: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    let hash = text.split_whitespace().next()?.to_lowercase();
+    if !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hash)
+    } else {
+        None
+    }
+}
+
+/// The checksum files Maven repos publish next to artifacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    /// the algorithms, strongest first
+    pub fn all() -> Vec<ChecksumAlgorithm> {
+        vec![
+            ChecksumAlgorithm::Sha512,
+            ChecksumAlgorithm::Sha256,
+            ChecksumAlgorithm::Sha1,
+        ]
+    }
+
+    /// the suffix of the checksum file, e.g. `.sha1`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha1 => ".sha1",
+            ChecksumAlgorithm::Sha256 => ".sha256",
+            ChecksumAlgorithm::Sha512 => ".sha512",
+        }
+    }
+
+    pub fn hex_digest(&self, bytes: &[u8]) -> String {
+        match self {
+            ChecksumAlgorithm::Sha1 => sha1_hex(bytes),
+            ChecksumAlgorithm::Sha256 => format!("{:x}", Sha256::digest(bytes)),
+            ChecksumAlgorithm::Sha512 => format!("{:x}", Sha512::digest(bytes)),
+        }
+    }
+
+    fn hasher(&self) -> Box<dyn DynDigest> {
+        match self {
+            ChecksumAlgorithm::Sha1 => Box::new(Sha1::new()),
+            ChecksumAlgorithm::Sha256 => Box::new(Sha256::new()),
+            ChecksumAlgorithm::Sha512 => Box::new(Sha512::new()),
+        }
+    }
+}
+
+/// the hex digests of everything the reader has, for each of the
+/// algorithms. It's read in chunks so a big file isn't held in memory
+pub fn hex_digests(
+    mut reader: impl Read,
+    algorithms: &[ChecksumAlgorithm],
+) -> io::Result<Vec<String>> {
+    let mut hashers: Vec<_> = algorithms.iter().map(|a| a.hasher()).collect();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buf[..n]);
+        }
+    }
+    Ok(hashers
+        .into_iter()
+        .map(|h| h.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+        .collect())
+}
+
+/// A checksum file fetched for an artifact
+#[derive(Debug, Clone)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub hash: String,
+    /// the file as served, so it can be stored next to the artifact
+    pub raw: Vec<u8>,
+}
+
+impl Checksum {
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        self.algorithm.hex_digest(bytes) == self.hash
+    }
+}
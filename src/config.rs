@@ -0,0 +1,101 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// where the config file is looked for when `--config` isn't given,
+/// relative to `$XDG_CONFIG_HOME` or `~/.config`
+pub const DEFAULT_CONFIG_FILE: &str = "mvn_crawler_carl/config.toml";
+
+/// The settings for one repo. Anything given on the command line wins
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub repo: Option<String>,
+    pub mirror: Option<String>,
+    pub crawl_db: Option<PathBuf>,
+    pub artifact_db: Option<PathBuf>,
+    pub max_threads: Option<usize>,
+    pub file_policy: Option<PathBuf>,
+    #[serde(default)]
+    pub include_groups: Vec<String>,
+    #[serde(default)]
+    pub exclude_groups: Vec<String>,
+    #[serde(default)]
+    pub include_artifacts: Vec<String>,
+    #[serde(default)]
+    pub exclude_artifacts: Vec<String>,
+}
+
+/// A TOML file of named profiles, e.g.,
+///
+/// ```toml
+/// [profiles.central]
+/// repo = "https://repo1.maven.org/maven2/"
+/// crawl_db = "~/data/maven/crawl_db/central"
+/// artifact_db = "~/data/maven/artifact_db/central"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// `~/.config/mvn_crawler_carl/config.toml`, or under `$XDG_CONFIG_HOME` if it's set
+pub fn default_config_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join(DEFAULT_CONFIG_FILE))
+}
+
+/// expand a leading `~/`, and make relative paths relative to the config file
+fn resolve_path(path: &Path, config_dir: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~")
+        && let Some(home) = env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    config_dir.join(path)
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        let text = read_to_string(path).with_context(|| format!("Reading {:?}", path))?;
+        let mut config: Config =
+            toml::from_str(&text).with_context(|| format!("Parsing {:?}", path))?;
+        let config_dir = path.parent().unwrap_or(Path::new("."));
+        for profile in config.profiles.values_mut() {
+            for p in [
+                &mut profile.crawl_db,
+                &mut profile.artifact_db,
+                &mut profile.file_policy,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *p = resolve_path(p, config_dir);
+            }
+        }
+        Ok(config)
+    }
+
+    /// the named profile
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        match self.profiles.get(name) {
+            Some(p) => Ok(p),
+            None => bail!(
+                "No profile {:?} in the config, the profiles are {:?}",
+                name,
+                self.profiles.keys().collect::<Vec<_>>()
+            ),
+        }
+    }
+}
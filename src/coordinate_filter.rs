@@ -0,0 +1,153 @@
+use anyhow::Result;
+use glob::Pattern;
+
+use crate::{args::Args, globs::compile_globs};
+
+/// Include and exclude globs on group and artifact ids, to scope a
+/// crawl or reify to some namespaces. A group pattern ending in `.*`
+/// also matches the group before it, so `org.apache.*` matches `org.apache`
+#[derive(Debug, Clone, Default)]
+pub struct CoordinateFilter {
+    include_groups: Vec<Pattern>,
+    exclude_groups: Vec<Pattern>,
+    include_artifacts: Vec<Pattern>,
+    exclude_artifacts: Vec<Pattern>,
+}
+
+fn group_matches(pattern: &Pattern, group: &str) -> bool {
+    pattern.matches(group)
+        || pattern
+            .as_str()
+            .strip_suffix(".*")
+            .is_some_and(|parent| parent == group)
+}
+
+/// the part of the pattern before any wildcards
+fn literal_prefix(pattern: &Pattern) -> &str {
+    let p = pattern.as_str();
+    match p.find(['*', '?', '[']) {
+        Some(i) => &p[..i],
+        None => p,
+    }
+}
+
+impl CoordinateFilter {
+    pub fn from_args(args: &Args) -> Result<CoordinateFilter> {
+        Ok(CoordinateFilter {
+            include_groups: compile_globs(args.include_groups(), "group")?,
+            exclude_groups: compile_globs(args.exclude_groups(), "group")?,
+            include_artifacts: compile_globs(args.include_artifacts(), "artifact")?,
+            exclude_artifacts: compile_globs(args.exclude_artifacts(), "artifact")?,
+        })
+    }
+
+    /// no globs, so everything passes
+    pub fn is_empty(&self) -> bool {
+        self.include_groups.is_empty()
+            && self.exclude_groups.is_empty()
+            && self.include_artifacts.is_empty()
+            && self.exclude_artifacts.is_empty()
+    }
+
+    /// should the group's metadata be crawled and reified?
+    pub fn allows_group(&self, group: &str) -> bool {
+        (self.include_groups.is_empty()
+            || self.include_groups.iter().any(|p| group_matches(p, group)))
+            && !self.exclude_groups.iter().any(|p| group_matches(p, group))
+    }
+
+    /// should the artifact be crawled and reified?
+    pub fn allows(&self, group: &str, artifact: &str) -> bool {
+        self.allows_group(group)
+            && (self.include_artifacts.is_empty()
+                || self.include_artifacts.iter().any(|p| p.matches(artifact)))
+            && !self.exclude_artifacts.iter().any(|p| p.matches(artifact))
+    }
+
+    /// could the directory (relative to the repo, e.g., `org/apache/`) hold
+    /// artifacts the filter allows? Without metadata there's no telling where
+    /// the group ends, so a directory is kept if it could be (part of) an
+    /// included group or be inside one, and is only pruned when its parent
+    /// is a group that an exclude ending in `*` covers along with everything under it
+    pub fn may_contain(&self, dir_path: &str) -> bool {
+        let parts: Vec<&str> = dir_path.split('/').filter(|p| !p.is_empty()).collect();
+        if parts.is_empty() {
+            return true;
+        }
+        let prefixes: Vec<String> = (1..=parts.len()).map(|k| parts[..k].join(".")).collect();
+        let path = &prefixes[prefixes.len() - 1];
+        let ancestors = &prefixes[..prefixes.len() - 1];
+
+        let excluded = self
+            .exclude_groups
+            .iter()
+            .any(|p| p.as_str().ends_with('*') && ancestors.iter().any(|a| group_matches(p, a)));
+        if excluded {
+            return false;
+        }
+        if self.include_groups.is_empty() {
+            return true;
+        }
+        self.include_groups.iter().any(|p| {
+            let lit = literal_prefix(p);
+            prefixes.iter().any(|q| group_matches(p, q))
+                // on the way down to an included group
+                || lit.strip_prefix(path.as_str()).is_some_and(|rest| rest.starts_with('.'))
+                // a wildcard further along could still match
+                || (lit.len() < p.as_str().len() && path.starts_with(lit))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include_groups: &[&str], exclude_groups: &[&str]) -> CoordinateFilter {
+        let globs = |g: &[&str]| g.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        CoordinateFilter {
+            include_groups: compile_globs(&globs(include_groups), "group").expect("good globs"),
+            exclude_groups: compile_globs(&globs(exclude_groups), "group").expect("good globs"),
+            ..CoordinateFilter::default()
+        }
+    }
+
+    #[test]
+    fn may_contain_included_groups() {
+        let f = filter(&["org.apache.*"], &[]);
+        assert!(f.may_contain(""));
+        // on the way down to the group
+        assert!(f.may_contain("org/"));
+        assert!(f.may_contain("org/apache/"));
+        assert!(f.may_contain("org/apache/commons/"));
+        assert!(!f.may_contain("com/"));
+        assert!(!f.may_contain("org/apachefoo/"));
+
+        // a wildcard further along could match anything after the literal part
+        let f = filter(&["org.*.core"], &[]);
+        assert!(f.may_contain("org/"));
+        assert!(f.may_contain("org/foo/bar/"));
+        assert!(!f.may_contain("com/"));
+
+        assert!(CoordinateFilter::default().may_contain("com/acme/"));
+    }
+
+    #[test]
+    fn may_contain_excluded_groups() {
+        let f = filter(&[], &["org.apache.*"]);
+        assert!(f.may_contain("org/"));
+        // could still be an artifact of `org` rather than the group
+        assert!(f.may_contain("org/apache/"));
+        assert!(!f.may_contain("org/apache/commons/"));
+        assert!(!f.may_contain("org/apache/commons/io/"));
+        assert!(f.may_contain("org/apachefoo/bar/"));
+
+        // without a trailing `*` the groups under it aren't covered
+        let f = filter(&[], &["org.apache"]);
+        assert!(f.may_contain("org/apache/commons/"));
+
+        let f = filter(&["org.*"], &["org.apache.*"]);
+        assert!(f.may_contain("org/eclipse/"));
+        assert!(!f.may_contain("org/apache/commons/"));
+    }
+}
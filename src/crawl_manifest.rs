@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+
+use crate::{
+    checkpoint::CrawlCheckpoint,
+    json_file::{read_json, write_json},
+    run_state::State,
+};
+
+pub const MANIFEST_FILE: &str = "crawl_manifest.json";
+
+/// Written to the crawl directory when a crawl finishes. A crawl
+/// directory without one is a crawl that died part way through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlManifest {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub repo_url: String,
+    pub mirror_url: Option<String>,
+    pub metadata_files: usize,
+    /// metadata files reused from the previous crawl
+    #[serde(default)]
+    pub not_modified: usize,
+    /// metadata files whose `.sha1` matched an existing copy
+    #[serde(default)]
+    pub checksum_skipped: usize,
+    pub pages_fetched: usize,
+    pub assets_fetched: usize,
+    pub total_bytes: usize,
+    pub error_cnt: usize,
+}
+
+impl CrawlManifest {
+    /// build the manifest for the crawl that just finished
+    pub fn from_state(state: State) -> Result<CrawlManifest> {
+        Ok(CrawlManifest {
+            started_at: state.crawl_started_at(),
+            finished_at: Utc::now(),
+            repo_url: state.repo_url()?,
+            mirror_url: state.mirror_url().clone(),
+            metadata_files: state.metadata_cnt(),
+            not_modified: state.not_modified_cnt(),
+            checksum_skipped: state.checksum_skip_cnt(),
+            pages_fetched: state.urls_fetched(),
+            assets_fetched: state.assets_fetched(),
+            total_bytes: state.get_total_bytes(),
+            error_cnt: state.error_cnt(),
+        })
+    }
+
+    /// does the crawl directory have a manifest?
+    pub fn exists(crawl_dir: &Path) -> bool {
+        crawl_dir.join(MANIFEST_FILE).is_file()
+    }
+
+    pub fn load(crawl_dir: &Path) -> Result<CrawlManifest> {
+        read_json(&crawl_dir.join(MANIFEST_FILE))
+    }
+
+    pub fn save(&self, crawl_dir: &Path) -> Result<()> {
+        write_json(&crawl_dir.join(MANIFEST_FILE), self)
+    }
+}
+
+/// print each crawl in the crawl db with a summary of its manifest
+pub fn list_crawls_to_console(state: State) -> Result<()> {
+    for dir in state.crawl_dirs()? {
+        let name = match dir.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if CrawlManifest::exists(&dir) {
+            match CrawlManifest::load(&dir) {
+                Ok(m) => println!(
+                    "{} complete started {} finished {} repo {} metadata files {} errors {}",
+                    name,
+                    m.started_at.format("%Y-%m-%d %H:%M:%S"),
+                    m.finished_at.format("%Y-%m-%d %H:%M:%S"),
+                    m.repo_url,
+                    m.metadata_files.separate_with_commas(),
+                    m.error_cnt.separate_with_commas()
+                ),
+                Err(e) => println!("{} unreadable manifest {}", name, e),
+            }
+        } else {
+            match CrawlCheckpoint::load(&dir) {
+                Ok(c) => println!(
+                    "{} incomplete started {} last checkpoint {} repo {} metadata files {} queue size {}",
+                    name,
+                    c.started_at.format("%Y-%m-%d %H:%M:%S"),
+                    c.saved_at.format("%Y-%m-%d %H:%M:%S"),
+                    c.repo_url,
+                    c.metadata_cnt.separate_with_commas(),
+                    c.queue.len().separate_with_commas()
+                ),
+                Err(_) => println!("{} incomplete", name),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use clap::ValueEnum;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use xmltree::Element as XmlElement;
+
+use anyhow::{Result, bail};
+
+use crate::{
+    http_stuff::{get_url, relative_path},
+    metadata::child_text,
+    response_data::GOLD_FILE,
+    run_state::State,
+};
+
+/// How reify works out which files to fetch for each version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactDiscovery {
+    /// guess the files from a fixed list of suffixes
+    #[default]
+    Suffixes,
+    /// fetch each version directory's listing and download what's there
+    Listing,
+    /// fetch each version's POM and download the files its packaging produces
+    Pom,
+}
+
+/// checksum and signature files are fetched along with the
+/// artifact they belong to, not on their own
+pub fn is_sidecar(name: &str) -> bool {
+    [".sha1", ".sha256", ".sha512", ".md5", ".asc"]
+        .iter()
+        .any(|s| name.ends_with(s))
+}
+
+/// fetch the version directory's listing and return the paths, relative
+/// to the repo, of the artifacts published in it
+pub fn list_version_dir(dir_path: &str, client: &mut Client, state: State) -> Result<Vec<String>> {
+    let repo_url = state.repo_url()?;
+    let url = format!("{}/{}", repo_url, dir_path);
+    let page = get_url(&repo_url, &url, client, state.clone())?;
+    if !page.mime_type().starts_with("text/html") {
+        bail!("Listing for {} is {} not HTML", url, page.mime_type());
+    }
+
+    let mut ret = vec![];
+    for link in page.html_to_file_links() {
+        let rel = relative_path(&link, state.clone())?;
+        let name = rel.rsplit('/').next().unwrap_or_default();
+        if name.is_empty() || name.starts_with(GOLD_FILE) || is_sidecar(name) {
+            continue;
+        }
+        ret.push(rel);
+    }
+    Ok(ret)
+}
+
+/// the `<packaging>` in a POM. Maven's default is `jar`, which
+/// is also used when the packaging is a property reference
+pub fn packaging_from_pom(pom: &[u8]) -> Result<String> {
+    let project = XmlElement::parse(pom)?;
+    let packaging = child_text(&project, "packaging").filter(|p| !p.contains("${"));
+    Ok(packaging.unwrap_or_else(|| "jar".to_string()))
+}
+
+/// the suffixes (after `{artifact}-{version}`) of the files a version
+/// with the packaging publishes, not counting the `.pom`
+pub fn suffixes_for_packaging(packaging: &str) -> Vec<String> {
+    let ret: Vec<&str> = match packaging {
+        "pom" => vec![],
+        "jar" | "bundle" | "maven-plugin" | "ejb" | "eclipse-plugin" | "maven-archetype" => {
+            vec![".jar", "-sources.jar", "-javadoc.jar"]
+        }
+        "aar" => vec![".aar", "-sources.jar", "-javadoc.jar"],
+        "war" => vec![".war"],
+        "ear" => vec![".ear"],
+        "rar" => vec![".rar"],
+        // Jenkins and NetBeans plugins publish the plugin and a jar
+        "hpi" => vec![".hpi", ".jar"],
+        "jpi" => vec![".jpi", ".jar"],
+        "nbm" => vec![".nbm", ".jar"],
+        // most custom packagings (e.g., from build extensions) produce
+        // a jar, but some use the packaging as the extension
+        other => return vec![format!(".{}", other), ".jar".to_string()],
+    };
+    ret.into_iter().map(|s| s.to_string()).collect()
+}
+
+/// split a version directory path like `org/example/foo/1.0/` into
+/// the artifact id and version
+pub fn artifact_and_version(dir_path: &str) -> Option<(String, String)> {
+    let mut parts = dir_path.trim_end_matches('/').rsplit('/');
+    let version = parts.next()?;
+    let artifact = parts.next()?;
+    if version.is_empty() || artifact.is_empty() {
+        return None;
+    }
+    Some((artifact.to_string(), version.to_string()))
+}
+
+/// split a version directory path like `org/example/foo/1.0/` into
+/// the group id and artifact id
+pub fn group_and_artifact(dir_path: &str) -> Option<(String, String)> {
+    let mut parts = dir_path.trim_end_matches('/').rsplit('/');
+    let _version = parts.next()?;
+    let artifact = parts.next()?;
+    let group: Vec<&str> = parts.rev().collect();
+    if artifact.is_empty() || group.is_empty() {
+        return None;
+    }
+    Some((group.join("."), artifact.to_string()))
+}
@@ -0,0 +1,348 @@
+use std::{
+    fs::read,
+    io::{Write, stdout},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use log::info;
+use rand::{rng, seq::SliceRandom};
+use reqwest::blocking::Client;
+use thousands::Separable;
+
+use crate::{
+    checksum::ChecksumAlgorithm,
+    http_stuff::{build_client, get_subbed_url, head_subbed_url, is_not_found},
+    plan_merge::{MergeCmd, plan_merge, version_dir_files},
+    run_state::State,
+};
+
+/// what a HEAD request said about a planned download
+enum Probe {
+    /// it's there, with its `Content-Length` if the server sent one
+    Found(Option<u64>),
+    Missing,
+    Failed,
+}
+
+/// what the plan would fetch
+#[derive(Default)]
+struct PlannedFetches {
+    packages: usize,
+    paths: Vec<String>,
+    /// version directories, whose files are only found while reifying
+    dirs: Vec<String>,
+}
+
+/// plan the reify without printing it, keeping the paths to download
+fn collect_plan(state: State) -> Result<PlannedFetches> {
+    let (tx, rx) = flume::bounded::<MergeCmd>(100);
+    let collector = thread::spawn(move || {
+        let mut ret = PlannedFetches::default();
+        // read until `plan_merge` drops the sender
+        for x in rx {
+            if let MergeCmd::Merge(group) = x {
+                ret.packages += 1;
+                for entry in group.entries() {
+                    match &entry.source_url {
+                        Some(dir) if entry.expand => ret.dirs.push(dir.clone()),
+                        Some(path) => ret.paths.push(path.clone()),
+                        None => {}
+                    }
+                }
+            }
+        }
+        ret
+    });
+
+    let planned = plan_merge(tx, state);
+    match collector.join() {
+        Ok(fetches) => planned.map(|_| fetches),
+        Err(_) => bail!("The plan collector panicked"),
+    }
+}
+
+/// run `work` on each item from `threads` threads, each with its own client
+fn on_threads<T, R>(
+    items: Vec<T>,
+    threads: usize,
+    work: impl Fn(T, &mut Client) -> R + Clone + Send + 'static,
+) -> Result<Vec<R>>
+where
+    T: Send + Sync + 'static,
+    R: Send + 'static,
+{
+    let (tx, rx) = flume::unbounded();
+    for item in items {
+        tx.send(item)?;
+    }
+    drop(tx);
+
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let rx = rx.clone();
+            let work = work.clone();
+            thread::spawn(move || {
+                let mut client = build_client();
+                rx.iter()
+                    .map(|item| work(item, &mut client))
+                    .collect::<Vec<R>>()
+            })
+        })
+        .collect();
+
+    let mut ret = vec![];
+    for w in workers {
+        match w.join() {
+            Ok(results) => ret.extend(results),
+            Err(_) => bail!("An estimate thread panicked"),
+        }
+    }
+    Ok(ret)
+}
+
+/// HEAD each path from `threads` threads, returning what each
+/// request found and how long it took
+fn probe_all(paths: Vec<String>, threads: usize, state: State) -> Result<Vec<(Probe, Duration)>> {
+    let repo_url = state.repo_url()?;
+    on_threads(paths, threads, move |path, client| {
+        let start = Instant::now();
+        let url = format!("{}/{}", repo_url, path);
+        let probe = match head_subbed_url(&url, client, state.clone()) {
+            Ok(len) => Probe::Found(len),
+            Err(e) if is_not_found(&e) => Probe::Missing,
+            Err(e) => {
+                info!("Couldn't get the headers of {} {}", url, e);
+                Probe::Failed
+            }
+        };
+        (probe, Instant::now().duration_since(start))
+    })
+}
+
+/// the files reify would fetch from a version directory, found the same
+/// way but without writing anything. Files already in the artifact db
+/// aren't fetched again
+fn expand_dir(dir: &str, client: &mut Client, state: State) -> Result<Vec<String>> {
+    let artifact_db = state.artifact_db()?;
+    let mut ret = vec![];
+    let files = version_dir_files(dir, client, &state, |pom_path, client| {
+        let on_disk = artifact_db.join(pom_path);
+        if on_disk.is_file() {
+            return Ok(read(&on_disk).ok());
+        }
+        ret.push(pom_path.to_string());
+        let url = format!("{}/{}", state.repo_url()?, pom_path);
+        match get_subbed_url(&url, client, state.clone()) {
+            Ok(page) => Ok(Some(page.data().clone())),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    })?;
+    ret.extend(files.into_iter().filter(|f| !artifact_db.join(f).is_file()));
+    Ok(ret)
+}
+
+/// what expanding version directories found
+#[derive(Default)]
+struct ExpandedDirs {
+    paths: Vec<String>,
+    missing: usize,
+    failed: usize,
+}
+
+/// expand each version directory from `threads` threads
+fn expand_all(dirs: Vec<String>, threads: usize, state: State) -> Result<ExpandedDirs> {
+    let expanded = on_threads(dirs, threads, move |dir, client| {
+        let files = expand_dir(&dir, client, state.clone());
+        (dir, files)
+    })?;
+    let mut ret = ExpandedDirs::default();
+    for (dir, files) in expanded {
+        match files {
+            Ok(files) => ret.paths.extend(files),
+            Err(e) if is_not_found(&e) => ret.missing += 1,
+            Err(e) => {
+                info!("Couldn't find the files in {} {}", dir, e);
+                ret.failed += 1;
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn hours_minutes_seconds(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}h {}m {}s", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+/// plan the reify, HEAD the planned downloads (or a sample of them) and
+/// print how much would be fetched, how much is missing, and how long
+/// it would take at `--max-threads` and `--bandwidth-mbps`
+pub fn estimate_to_console(state: State) -> Result<()> {
+    let PlannedFetches {
+        packages,
+        mut paths,
+        mut dirs,
+    } = collect_plan(state.clone())?;
+
+    // find the files in the version directories (or a sample of them)
+    // the way reify would, and scale up to all of them
+    let dir_cnt = dirs.len();
+    if let Some(sample) = state.sample()
+        && sample < dir_cnt
+    {
+        dirs.shuffle(&mut rng());
+        dirs.truncate(sample);
+    }
+    let expanded_cnt = dirs.len();
+    if expanded_cnt > 0 {
+        info!(
+            "Finding the files in {} of {} version directories",
+            expanded_cnt.separate_with_commas(),
+            dir_cnt.separate_with_commas()
+        );
+    }
+    let expanded = expand_all(
+        dirs,
+        state.max_threads().min(expanded_cnt).max(1),
+        state.clone(),
+    )?;
+    let dir_scale = if expanded_cnt == 0 {
+        0f64
+    } else {
+        dir_cnt as f64 / expanded_cnt as f64
+    };
+    let dir_file_cnt = (expanded.paths.len() as f64 * dir_scale).round() as usize;
+    let planned_cnt = paths.len() + dir_file_cnt;
+    paths.extend(expanded.paths);
+    if let Some(sample) = state.sample()
+        && sample < planned_cnt
+    {
+        paths.shuffle(&mut rng());
+        paths.truncate(sample);
+    }
+    let asked_cnt = paths.len();
+    let threads = state.max_threads().min(asked_cnt).max(1);
+    info!(
+        "Asking about {} of {} planned downloads with {} threads",
+        asked_cnt.separate_with_commas(),
+        planned_cnt.separate_with_commas(),
+        threads
+    );
+
+    let start = Instant::now();
+    let probes = probe_all(paths, threads, state.clone())?;
+    info!(
+        "Got the headers in {:?}",
+        Instant::now().duration_since(start)
+    );
+
+    let mut found = 0usize;
+    let mut sized = 0usize;
+    let mut missing = 0usize;
+    let mut failed = 0usize;
+    let mut bytes = 0u64;
+    let mut request_time = Duration::ZERO;
+    for (probe, took) in &probes {
+        request_time += *took;
+        match probe {
+            Probe::Found(len) => {
+                found += 1;
+                if let Some(len) = len {
+                    sized += 1;
+                    bytes += len;
+                }
+            }
+            Probe::Missing => missing += 1,
+            Probe::Failed => failed += 1,
+        }
+    }
+
+    // scale what was asked about up to the whole plan. Files without a
+    // `Content-Length` are assumed to be the average size
+    let answered = found + missing;
+    let scale = if answered == 0 {
+        0f64
+    } else {
+        planned_cnt as f64 / answered as f64
+    };
+    let est_found = found as f64 * scale;
+    let est_missing = (missing as f64 * scale).round() as u64;
+    let avg_size = if sized == 0 {
+        0f64
+    } else {
+        bytes as f64 / sized as f64
+    };
+    let est_bytes = (avg_size * est_found).round() as u64;
+
+    // reify gets each file and asks for checksum files, strongest first,
+    // until it finds one, a missing file is a single request, and each
+    // version directory is one more. Count the checksum requests at most
+    let avg_request = if probes.is_empty() {
+        Duration::ZERO
+    } else {
+        request_time / probes.len() as u32
+    };
+    let requests = est_found * (1 + ChecksumAlgorithm::all().len()) as f64
+        + est_missing as f64
+        + dir_cnt as f64;
+    let est_requests = avg_request.mul_f64(requests / state.max_threads() as f64);
+    let est_transfer =
+        Duration::from_secs_f64(est_bytes as f64 * 8f64 / (state.bandwidth_mbps() * 1_000_000f64));
+
+    let mut out = stdout().lock();
+    write!(
+        out,
+        "Planned: {} packages, {} downloads",
+        packages.separate_with_commas(),
+        planned_cnt.separate_with_commas()
+    )?;
+    if dir_cnt > 0 {
+        write!(
+            out,
+            " ({} of them from {} version directories)",
+            dir_file_cnt.separate_with_commas(),
+            dir_cnt.separate_with_commas()
+        )?;
+    }
+    writeln!(out)?;
+    if expanded.missing + expanded.failed > 0 {
+        writeln!(
+            out,
+            "Of {} version directories looked in: {} missing, {} failed (not estimated)",
+            expanded_cnt.separate_with_commas(),
+            expanded.missing.separate_with_commas(),
+            expanded.failed.separate_with_commas()
+        )?;
+    }
+    writeln!(
+        out,
+        "Asked about {} downloads: {} found, {} missing, {} failed, {} bytes ({} without a size)",
+        asked_cnt.separate_with_commas(),
+        found.separate_with_commas(),
+        missing.separate_with_commas(),
+        failed.separate_with_commas(),
+        bytes.separate_with_commas(),
+        (found - sized).separate_with_commas()
+    )?;
+    writeln!(
+        out,
+        "Estimated: {} bytes ({:.2} gb), {} missing files",
+        est_bytes.separate_with_commas(),
+        est_bytes as f64 / (1024f64 * 1024f64 * 1024f64),
+        est_missing.separate_with_commas()
+    )?;
+    writeln!(
+        out,
+        "Estimated time at {} threads and {} Mbit/s: {} ({} of requests, {} of transfer)",
+        state.max_threads(),
+        state.bandwidth_mbps(),
+        hours_minutes_seconds(est_requests + est_transfer),
+        hours_minutes_seconds(est_requests),
+        hours_minutes_seconds(est_transfer)
+    )?;
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    args::Args,
+    discovery::ArtifactDiscovery,
+    globs::compile_globs,
+    json_file::{RecordedPolicy, read_json},
+    plan_merge::suffixes,
+};
+
+pub const FILE_POLICY_FILE: &str = "file_policy.json";
+
+/// Which files reify fetches for each version. Loaded from
+/// `--file-policy` with `--suffix`, `--include-file` and
+/// `--exclude-file` layered on top
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePolicy {
+    /// what to guess after `{artifact}-{version}` with `--discovery suffixes`
+    #[serde(default = "default_suffixes")]
+    pub suffixes: Vec<String>,
+    /// globs on the file name. If there are any, only matching files are fetched
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// globs on the file name. Matching files are never fetched
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// how the files were found, from `--discovery`. Policies recorded
+    /// before it was kept were made with the default
+    #[serde(default)]
+    pub discovery: ArtifactDiscovery,
+    #[serde(skip)]
+    include_patterns: Vec<Pattern>,
+    #[serde(skip)]
+    exclude_patterns: Vec<Pattern>,
+}
+
+fn default_suffixes() -> Vec<String> {
+    suffixes().into_iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for FilePolicy {
+    fn default() -> Self {
+        FilePolicy {
+            suffixes: default_suffixes(),
+            include: vec![],
+            exclude: vec![],
+            discovery: ArtifactDiscovery::default(),
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+        }
+    }
+}
+
+impl FilePolicy {
+    /// build the policy from the policy file (if any) and the command line
+    pub fn from_args(args: &Args) -> Result<FilePolicy> {
+        let mut ret = match args.file_policy() {
+            Some(path) => read_json(path)?,
+            None => FilePolicy::default(),
+        };
+        if !args.suffixes().is_empty() {
+            ret.suffixes = args.suffixes().clone();
+        }
+        ret.include.extend(args.include_files().iter().cloned());
+        ret.exclude.extend(args.exclude_files().iter().cloned());
+        ret.discovery = args.discovery();
+        ret.include_patterns = compile_globs(&ret.include, "file")?;
+        ret.exclude_patterns = compile_globs(&ret.exclude, "file")?;
+        Ok(ret)
+    }
+
+    /// should the file (the last part of the path) be fetched?
+    pub fn allows(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        (self.include_patterns.is_empty() || self.include_patterns.iter().any(|p| p.matches(name)))
+            && !self.exclude_patterns.iter().any(|p| p.matches(name))
+    }
+
+    /// would the two policies fetch the same files?
+    pub fn same_files_as(&self, other: &FilePolicy) -> bool {
+        self.suffixes == other.suffixes
+            && self.include == other.include
+            && self.exclude == other.exclude
+            && self.discovery == other.discovery
+    }
+}
+
+impl RecordedPolicy for FilePolicy {
+    const FILE_NAME: &'static str = FILE_POLICY_FILE;
+}
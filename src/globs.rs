@@ -0,0 +1,14 @@
+use anyhow::{Result, bail};
+use glob::Pattern;
+
+/// compile the globs, naming what they match (e.g., `file`) if one is bad
+pub fn compile_globs(globs: &[String], what: &str) -> Result<Vec<Pattern>> {
+    let mut ret = vec![];
+    for g in globs {
+        match Pattern::new(g) {
+            Ok(p) => ret.push(p),
+            Err(e) => bail!("Bad {} pattern {:?}, {}", what, g, e),
+        }
+    }
+    Ok(ret)
+}
@@ -1,18 +1,54 @@
 use std::{
+    fmt,
+    fs::read,
+    path::Path,
     thread::{self, sleep},
     time::Duration,
 };
 
 use crate::{
+    checksum::{Checksum, ChecksumAlgorithm, parse_checksum, sha1_hex},
+    metadata::{MetadataKind, metadata_kind},
     plan_merge::version_from_metadata,
-    response_data::{GOLD_FILE, ResponseData},
+    response_data::{CacheHeaders, GOLD_FILE, ResponseData, link_or_copy, replace_with_copy},
     run_state::State,
+    snapshot::is_snapshot,
 };
 use anyhow::{Result, bail};
-use log::{error, info};
-use reqwest::blocking::{Client, ClientBuilder, Response};
+use log::{debug, error, info};
+use reqwest::{
+    StatusCode,
+    blocking::{Client, ClientBuilder, Response},
+    header::{CONTENT_LENGTH, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
 use thousands::Separable;
 
+/// The server answered, but not with success
+#[derive(Debug)]
+pub struct HttpStatusError {
+    pub url: String,
+    pub status: StatusCode,
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to load {} status {}", self.url, self.status)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// did the request fail because the URL doesn't exist (as opposed
+/// to a network error or the server having problems)?
+pub fn is_not_found(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<HttpStatusError>() {
+        Some(HttpStatusError { status, .. }) => {
+            *status == StatusCode::NOT_FOUND || *status == StatusCode::GONE
+        }
+        None => false,
+    }
+}
+
 pub fn build_client() -> Client {
     ClientBuilder::new()
         .user_agent("Spice Labs https://spicelabs.io")
@@ -27,7 +63,7 @@ pub fn should_do_links(links: &Vec<String>, state: State) -> bool {
         true
     } else if state.queue_len() < 10_000 || links.len() > 15 {
         for link in links {
-            state.push_page(&link);
+            state.push_page(link);
         }
 
         false
@@ -36,9 +72,14 @@ pub fn should_do_links(links: &Vec<String>, state: State) -> bool {
     }
 }
 
-pub fn process_page(url: String, client: &mut Client, depth: usize, state: State) -> Result<usize> {
+pub fn process_page(url: String, client: &mut Client, state: State) -> Result<usize> {
     let mut processed_cnt = 0;
 
+    // already crawled before the crawl was resumed
+    if state.is_visited(&url) {
+        return Ok(processed_cnt);
+    }
+
     let page: ResponseData = get_url(&state.repo_url()?, &url, client, state.clone())?;
 
     if page.mime_type().starts_with("text/html") {
@@ -52,18 +93,57 @@ pub fn process_page(url: String, client: &mut Client, depth: usize, state: State
         let mut load_links = true;
 
         // we found a maven metadata file
-        if gold_links.len() > 0 {
+        if !gold_links.is_empty() {
             // for each one (there should only by 1)
             for gold_link in gold_links {
                 // get the file from the server
-                match get_subbed_url(&gold_link, client, state.clone()) {
-                    Ok(page) => {
-                        match version_from_metadata(page.data()) {
-                            Ok(_to_load) => {
-                                // it's valid, save it and don't load links
-                                load_links = false;
+                match fetch_metadata(&gold_link, client, state.clone()) {
+                    Ok(MetadataFetch::FilteredOut) => {
+                        // outside the --include/--exclude globs
+                        load_links = false;
+                        debug!("Skipping filtered out {}", gold_link);
+                    }
+                    Ok(MetadataFetch::Reused) => {
+                        // unchanged since the previous crawl, which
+                        // only saved it because it was valid
+                        state.inc_metadata_cnt();
+                        let saved = read(
+                            state
+                                .crawl_db_dest_dir()
+                                .join(relative_path(&gold_link, state.clone())?),
+                        )?;
+                        let kind = metadata_kind(&saved)?;
+                        load_links = kind == MetadataKind::Group;
+                        if kind == MetadataKind::Artifact && is_allowed(&saved, &state)? {
+                            fetch_snapshot_metadata(&gold_link, &saved, client, state.clone());
+                        }
+                    }
+                    Ok(MetadataFetch::Fetched(page)) => {
+                        match metadata_kind(page.data()) {
+                            Ok(kind) => {
+                                // it's valid, save it. A group's plugin metadata
+                                // sits above its artifacts so keep going into the
+                                // page, otherwise don't load links
+                                load_links = kind == MetadataKind::Group;
+
+                                // outside the --include/--exclude globs
+                                if kind == MetadataKind::Artifact
+                                    && !is_allowed(page.data(), &state)?
+                                {
+                                    debug!("Skipping filtered out {}", gold_link);
+                                    continue;
+                                }
 
                                 page.save()?;
+                                state.inc_metadata_cnt();
+                                if kind == MetadataKind::Artifact {
+                                    fetch_snapshot_metadata(
+                                        &gold_link,
+                                        page.data(),
+                                        client,
+                                        state.clone(),
+                                    );
+                                }
                             }
                             Err(_e) => {
                                 // if we can't parse the metadata, then continue
@@ -74,33 +154,227 @@ pub fn process_page(url: String, client: &mut Client, depth: usize, state: State
                         }
                     }
                     Err(e) => {
+                        state.inc_error_cnt();
                         error!("Failed to fetch {} err {:?}", gold_link, e)
                     }
                 }
             }
         }
 
-        if load_links {
-            if should_do_links(&links, state.clone()) {
-                for link in links {
-                    if !link.ends_with(".xml") {
-                        processed_cnt += 1;
-                        match process_page(link.clone(), client, depth + 1, state.clone()) {
-                            Ok(sub_cnt) => {
-                                processed_cnt += sub_cnt;
-                            }
-                            Err(e) => {
-                                error!("Failed to load {}, error {:?}", link, e);
-                            }
+        // don't go into directories that can't hold the groups we want
+        let filter = state.coordinate_filter()?;
+        let links: Vec<String> = links
+            .into_iter()
+            .filter(|l| {
+                !l.ends_with('/')
+                    || relative_path(l, state.clone()).is_ok_and(|p| filter.may_contain(&p))
+            })
+            .collect();
+
+        if load_links && should_do_links(&links, state.clone()) {
+            for link in links {
+                if !link.ends_with(".xml") {
+                    processed_cnt += 1;
+                    match process_page(link.clone(), client, state.clone()) {
+                        Ok(sub_cnt) => {
+                            processed_cnt += sub_cnt;
+                        }
+                        Err(e) => {
+                            state.inc_error_cnt();
+                            error!("Failed to load {}, error {:?}", link, e);
                         }
                     }
                 }
             }
         }
     }
+    state.mark_visited(&url);
     Ok(processed_cnt)
 }
 
+/// does the artifact metadata's group and artifact pass the coordinate filter?
+fn is_allowed(metadata: &[u8], state: &State) -> Result<bool> {
+    let md = version_from_metadata(metadata)?;
+    Ok(state
+        .coordinate_filter()?
+        .allows(&md.group_id, &md.artifact_id))
+}
+
+/// fetch and save the metadata in each snapshot version's directory,
+/// which names the timestamped files of the latest build
+fn fetch_snapshot_metadata(gold_link: &str, metadata: &[u8], client: &mut Client, state: State) {
+    let versions = match version_from_metadata(metadata) {
+        Ok(md) => md.versions,
+        Err(_) => return,
+    };
+    let dir = gold_link.trim_end_matches(GOLD_FILE);
+    for v in versions.iter().filter(|v| is_snapshot(v)) {
+        let link = format!("{}{}/{}", dir, v, GOLD_FILE);
+        let saved = match fetch_metadata(&link, client, state.clone()) {
+            Ok(MetadataFetch::Reused) => Ok(()),
+            Ok(MetadataFetch::FilteredOut) => continue,
+            Ok(MetadataFetch::Fetched(page)) => page.save(),
+            Err(e) => Err(e),
+        };
+        match saved {
+            Ok(_) => {
+                state.inc_metadata_cnt();
+            }
+            // snapshots deployed without timestamps may not have any
+            Err(e) if is_not_found(&e) => debug!("No version metadata at {}", link),
+            Err(e) => {
+                state.inc_error_cnt();
+                error!("Failed to fetch {} err {:?}", link, e)
+            }
+        }
+    }
+}
+
+/// the path of the URL relative to the repo
+pub fn relative_path(url: &str, state: State) -> Result<String> {
+    let repo_url = state.repo_url()?;
+    match url.strip_prefix(&repo_url) {
+        Some(v) => Ok(v.trim_start_matches('/').to_string()),
+        None => bail!("{} isn't in the repo {}", url, repo_url),
+    }
+}
+
+/// fetch the `.sha1` for the metadata file and, if it matches the copy
+/// in the previous crawl or the artifact db, put that copy in this crawl.
+/// Returns `true` if a copy was reused
+fn reuse_if_checksum_matches(
+    gold_link: &str,
+    rel: &str,
+    previous: Option<&Path>,
+    client: &mut Client,
+    state: State,
+) -> Result<bool> {
+    let mut candidates = vec![];
+    if let Some(previous) = previous {
+        candidates.push(previous.to_path_buf());
+    }
+    if let Ok(artifact_db) = state.artifact_db() {
+        let in_artifact_db = artifact_db.join(rel);
+        if in_artifact_db.is_file() {
+            candidates.push(in_artifact_db);
+        }
+    }
+    if candidates.is_empty() {
+        return Ok(false);
+    }
+
+    let remote_sha1 = match get_subbed_url(&format!("{}.sha1", gold_link), client, state.clone())
+        .ok()
+        .and_then(|page| parse_checksum(page.data()))
+    {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+
+    let dest = state.crawl_db_dest_dir().join(rel);
+    for candidate in candidates {
+        let bytes = match read(&candidate) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if sha1_hex(&bytes) == remote_sha1 {
+            if Some(candidate.as_path()) == previous {
+                link_or_copy(&candidate, &dest)?;
+            } else {
+                // reify rewrites the artifact db's metadata, so don't link to it
+                replace_with_copy(&candidate, &dest)?;
+            }
+            state.inc_checksum_skip_cnt();
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// What `fetch_metadata` did
+pub enum MetadataFetch {
+    /// new to this crawl, or changed since the copies we have
+    Fetched(ResponseData),
+    /// a copy from the previous crawl or the artifact db was put in this crawl
+    Reused,
+    /// a copy we have is of an artifact outside the `--include`/`--exclude`
+    /// globs, so nothing was fetched or put in this crawl
+    FilteredOut,
+}
+
+/// is the copy of the metadata in the previous crawl or the artifact db
+/// of an artifact the coordinate filter leaves out?
+fn filtered_out_before(rel: &str, previous: Option<&Path>, state: &State) -> Result<bool> {
+    if state.coordinate_filter()?.is_empty() {
+        return Ok(false);
+    }
+    let mut candidates: Vec<_> = previous.map(Path::to_path_buf).into_iter().collect();
+    if let Ok(artifact_db) = state.artifact_db() {
+        candidates.push(artifact_db.join(rel));
+    }
+    for candidate in candidates {
+        let bytes = match read(&candidate) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if metadata_kind(&bytes).is_ok_and(|k| k == MetadataKind::Artifact) {
+            return Ok(!is_allowed(&bytes, state)?);
+        }
+    }
+    Ok(false)
+}
+
+/// fetch a `maven-metadata.xml` file. If a copy in the previous crawl or
+/// the artifact db shows it's of an artifact the coordinate filter leaves out,
+/// nothing is fetched. If the previous crawl has the file, make the request
+/// conditional and, if the server says it hasn't changed, link the previous
+/// crawl's copy into this crawl. Without cache headers, the `.sha1` is
+/// compared with the copies in the previous crawl and the artifact db
+pub fn fetch_metadata(gold_link: &str, client: &mut Client, state: State) -> Result<MetadataFetch> {
+    let rel = relative_path(gold_link, state.clone())?;
+    let previous = state
+        .previous_crawl()
+        .map(|dir| dir.join(&rel))
+        .filter(|p| p.is_file());
+    if filtered_out_before(&rel, previous.as_deref(), &state)? {
+        return Ok(MetadataFetch::FilteredOut);
+    }
+    if state.full_crawl() {
+        return get_subbed_url(gold_link, client, state).map(MetadataFetch::Fetched);
+    }
+    let headers = previous.as_ref().and_then(|p| CacheHeaders::load(p));
+
+    match (previous, headers) {
+        (Some(previous), Some(headers)) => {
+            match get_subbed_url_if_changed(gold_link, Some(&headers), client, state.clone())? {
+                Some(page) => Ok(MetadataFetch::Fetched(page)),
+                None => {
+                    let dest = state.crawl_db_dest_dir().join(&rel);
+                    link_or_copy(&previous, &dest)?;
+                    link_or_copy(
+                        &CacheHeaders::path_for(&previous),
+                        &CacheHeaders::path_for(&dest),
+                    )?;
+                    state.inc_not_modified_cnt();
+                    Ok(MetadataFetch::Reused)
+                }
+            }
+        }
+        (previous, _) => {
+            if reuse_if_checksum_matches(
+                gold_link,
+                &rel,
+                previous.as_deref(),
+                client,
+                state.clone(),
+            )? {
+                return Ok(MetadataFetch::Reused);
+            }
+            get_subbed_url(gold_link, client, state).map(MetadataFetch::Fetched)
+        }
+    }
+}
+
 /// based on the number of threads in 429, delay
 fn delay_429(state: State) {
     let threads_in_429 = state.get_429_cnt();
@@ -112,22 +386,124 @@ fn delay_429(state: State) {
 }
 
 pub fn get_subbed_url(url: &str, client: &mut Client, state: State) -> Result<ResponseData> {
+    match get_subbed_url_if_changed(url, None, client, state)? {
+        Some(v) => Ok(v),
+        None => bail!("Got Not Modified for unconditional request {}", url),
+    }
+}
+
+/// get the URL from the mirror, falling back to the repo. If `cache_headers`
+/// is set, the request is conditional and `None` is returned if the
+/// server says the asset hasn't changed
+pub fn get_subbed_url_if_changed(
+    url: &str,
+    cache_headers: Option<&CacheHeaders>,
+    client: &mut Client,
+    state: State,
+) -> Result<Option<ResponseData>> {
     let ret = match state.mirror_url() {
         Some(mirror) => {
             let first = format!("{}{}", mirror, &url[state.repo_url()?.len()..]);
-            match get_url(&mirror, &first, client, state.clone()) {
+            match get_url_if_changed(mirror, &first, cache_headers, client, state.clone()) {
                 Ok(v) => Ok(v),
-                Err(_) => get_url(&state.repo_url()?, url, client, state.clone()),
+                Err(_) => get_url_if_changed(
+                    &state.repo_url()?,
+                    url,
+                    cache_headers,
+                    client,
+                    state.clone(),
+                ),
             }
         }
-        None => get_url(&state.repo_url()?, url, client, state.clone()),
+        None => get_url_if_changed(
+            &state.repo_url()?,
+            url,
+            cache_headers,
+            client,
+            state.clone(),
+        ),
     };
-    if ret.is_ok() {
+    if let Ok(Some(_)) = ret {
         state.inc_asset_fetch_cnt();
     }
     ret
 }
 
+/// fetch the strongest checksum file published next to the URL. Checksum
+/// files the repo said it doesn't have are remembered in the negative cache
+/// and not asked for again. Any other error is returned, so the artifact
+/// isn't taken as unverified because of a timeout.
+/// If `from_origin` is set, skip the mirror
+pub fn fetch_checksums(
+    url: &str,
+    from_origin: bool,
+    client: &mut Client,
+    state: State,
+) -> Result<Vec<Checksum>> {
+    let negative_cache = state.negative_cache()?;
+    for algorithm in ChecksumAlgorithm::all() {
+        let checksum_url = format!("{}{}", url, algorithm.extension());
+        let path = relative_path(&checksum_url, state.clone())?;
+        if negative_cache.is_known_missing(&path) {
+            continue;
+        }
+        let fetched = if from_origin {
+            get_url(&state.repo_url()?, &checksum_url, client, state.clone())
+        } else {
+            get_subbed_url(&checksum_url, client, state.clone())
+        };
+        match fetched {
+            Ok(page) => {
+                if negative_cache.contains(&path) {
+                    negative_cache.record_found(&path)?;
+                }
+                if let Some(hash) = parse_checksum(page.data()) {
+                    return Ok(vec![Checksum {
+                        algorithm,
+                        hash,
+                        raw: page.data().clone(),
+                    }]);
+                }
+            }
+            Err(e) if is_not_found(&e) => negative_cache.record_missing(&path)?,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(vec![])
+}
+
+/// fetch an artifact and its checksum files and verify the artifact against
+/// them. If the copy from the mirror doesn't match, fetch it from the repo
+pub fn get_verified_url(
+    url: &str,
+    client: &mut Client,
+    state: State,
+) -> Result<(ResponseData, Vec<Checksum>)> {
+    let loaded = get_subbed_url(url, client, state.clone())?;
+    let checksums = fetch_checksums(url, false, client, state.clone())?;
+    if checksums.iter().all(|c| c.matches(loaded.data())) {
+        if checksums.is_empty() {
+            state.inc_unverified_cnt();
+        }
+        return Ok((loaded, checksums));
+    }
+
+    if state.mirror_url().is_some() {
+        info!("Checksum mismatch for {} from mirror, trying repo", url);
+        let loaded = get_url(&state.repo_url()?, url, client, state.clone())?;
+        let checksums = fetch_checksums(url, true, client, state.clone())?;
+        if checksums.iter().all(|c| c.matches(loaded.data())) {
+            if checksums.is_empty() {
+                state.inc_unverified_cnt();
+            }
+            return Ok((loaded, checksums));
+        }
+    }
+
+    state.inc_checksum_mismatch_cnt();
+    bail!("Checksum mismatch for {}", url)
+}
+
 /// remove double '/' from the URL
 fn fix_url(url: &str) -> String {
     let mut ret = String::new();
@@ -157,6 +533,21 @@ pub fn get_url(
     client: &mut Client,
     state: State,
 ) -> Result<ResponseData> {
+    match get_url_if_changed(server_prefix, url, None, client, state)? {
+        Some(v) => Ok(v),
+        None => bail!("Got Not Modified for unconditional request {}", url),
+    }
+}
+
+/// get the URL. If `cache_headers` is set, send `If-Modified-Since`
+/// and `If-None-Match` and return `None` on a 304
+pub fn get_url_if_changed(
+    server_prefix: &str,
+    url: &str,
+    cache_headers: Option<&CacheHeaders>,
+    client: &mut Client,
+    state: State,
+) -> Result<Option<ResponseData>> {
     delay_429(state.clone());
 
     let url = fix_url(url);
@@ -165,7 +556,16 @@ pub fn get_url(
         // loop 6 times trying to get the page...
         let mut try_cnt = 0;
         loop {
-            let val = client.get(&url).send();
+            let mut request = client.get(&url);
+            if let Some(cache_headers) = cache_headers {
+                if let Some(last_modified) = &cache_headers.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+                if let Some(etag) = &cache_headers.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+            }
+            let val = request.send();
             match val {
                 Ok(x) => {
                     break x;
@@ -185,23 +585,42 @@ pub fn get_url(
         let cnt_429 = state.inc_429_cnt();
         info!("429 count {} url {}", cnt_429, url);
         sleep(Duration::from_millis(350));
-        let ret = get_url(server_prefix, &url, client, state.clone());
+        let ret = get_url_if_changed(server_prefix, &url, cache_headers, client, state.clone());
         state.dec_429_cnt();
         return ret;
     }
 
+    if response_code == StatusCode::NOT_MODIFIED && cache_headers.is_some() {
+        state.inc_fetch_cnt();
+        return Ok(None);
+    }
+
     if !info.status().is_success() {
-        bail!("Failed to load {} status {}", url, info.status());
+        return Err(HttpStatusError {
+            url,
+            status: info.status(),
+        }
+        .into());
     }
 
     let cnt = state.inc_fetch_cnt();
-    if cnt % 10_000 == 0 {
+    if cnt.is_multiple_of(10_000) {
         info!("Fetch {} cnt {}", url, cnt.separate_with_commas());
     }
     let content_type = match info.headers().get("content-type") {
         Some(v) => v.to_str()?.to_string(),
         None => "????".to_string(),
     };
+    let header_string = |name| {
+        info.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let cache_headers = CacheHeaders {
+        last_modified: header_string(LAST_MODIFIED),
+        etag: header_string(ETAG),
+    };
     let bytes = info.bytes()?;
     let v: Vec<u8> = bytes.into();
     ResponseData::new(
@@ -209,8 +628,72 @@ pub fn get_url(
         server_prefix,
         v,
         content_type.to_string(),
+        cache_headers,
         state.clone(),
     )
+    .map(Some)
+}
+
+/// ask for the URL's headers from the mirror, falling back to the repo,
+/// and return its `Content-Length`, if the server sent one
+pub fn head_subbed_url(url: &str, client: &mut Client, state: State) -> Result<Option<u64>> {
+    match state.mirror_url() {
+        Some(mirror) => {
+            let first = format!("{}{}", mirror, &url[state.repo_url()?.len()..]);
+            match head_url(&first, client, state.clone()) {
+                Ok(v) => Ok(v),
+                Err(_) => head_url(url, client, state),
+            }
+        }
+        None => head_url(url, client, state),
+    }
+}
+
+/// send a HEAD request for the URL and return its `Content-Length`
+pub fn head_url(url: &str, client: &mut Client, state: State) -> Result<Option<u64>> {
+    delay_429(state.clone());
+
+    let url = fix_url(url);
+
+    let info: Response = {
+        let mut try_cnt = 0;
+        loop {
+            match client.head(&url).send() {
+                Ok(x) => break x,
+                Err(e) => {
+                    if try_cnt > 5 {
+                        bail!("Failed to get headers of {} error {:?}", url, e);
+                    }
+                    try_cnt += 1;
+                }
+            }
+        }
+    };
+
+    if info.status().as_u16() == 429 {
+        let cnt_429 = state.inc_429_cnt();
+        info!("429 count {} url {}", cnt_429, url);
+        sleep(Duration::from_millis(350));
+        let ret = head_url(&url, client, state.clone());
+        state.dec_429_cnt();
+        return ret;
+    }
+
+    if !info.status().is_success() {
+        return Err(HttpStatusError {
+            url,
+            status: info.status(),
+        }
+        .into());
+    }
+
+    state.inc_fetch_cnt();
+    // not `content_length()`, which is the length of the (empty) body
+    Ok(info
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok()))
 }
 
 pub fn spawn_a_page(state: State) {
@@ -219,26 +702,18 @@ pub fn spawn_a_page(state: State) {
     let x = state.inc_running_threads();
     thread::spawn(move || {
         let mut client = build_client();
-        let mut page_loop = 0;
         while let Some(page_to_process) = state.next_page() {
-            page_loop += 1;
-            match process_page(page_to_process.clone(), &mut client, 0, state.clone()) {
-                Ok(processed_cnt) => {
-                    if false && page_loop % 100 == 0 {
-                        info!(
-                            "Page process thread {} got to top, cnt {} page {} queue size {}",
-                            x,
-                            processed_cnt.separate_with_commas(),
-                            page_to_process.separate_with_commas(),
-                            state.queue_len().separate_with_commas()
-                        );
-                    }
+            match process_page(page_to_process.clone(), &mut client, state.clone()) {
+                Ok(_) => {}
+                Err(e) => {
+                    state.inc_error_cnt();
+                    error!(
+                        "Page process thread {} got to top with error {:?}, {}",
+                        x, e, page_to_process
+                    )
                 }
-                Err(e) => error!(
-                    "Page process thread {} got to top with error {:?}, {}",
-                    x, e, page_to_process
-                ),
             }
+            state.finish_page(&page_to_process);
         }
         state.dec_running_threads();
     });
@@ -249,11 +724,13 @@ pub fn periodic_info(state: State) {
         while state.thread_cnt() > 0 {
             sleep(Duration::from_secs(30));
             info!(
-                "At {:?} threads {} urls {} assets {} queue size {} loaded {}gb",
+                "At {:?} threads {} urls {} assets {} not modified {} checksum skipped {} queue size {} loaded {}gb",
                 state.run_duration(),
                 state.thread_cnt(),
                 state.urls_fetched().separate_with_commas(),
                 state.assets_fetched().separate_with_commas(),
+                state.not_modified_cnt().separate_with_commas(),
+                state.checksum_skip_cnt().separate_with_commas(),
                 state.queue_len().separate_with_commas(),
                 (state.get_total_bytes() / (1024 * 1024 * 1024)).separate_with_commas()
             );
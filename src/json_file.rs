@@ -0,0 +1,132 @@
+use std::{
+    fs::{File, OpenOptions, remove_file, rename},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use log::warn;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::response_data::temp_path_for;
+
+/// read and parse a JSON file
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let file = File::open(path).with_context(|| format!("Opening {:?}", path))?;
+    let ret = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Parsing {:?}", path))?;
+    Ok(ret)
+}
+
+/// write the value as JSON. The file is written next to the
+/// destination and renamed so a kill mid-write leaves the
+/// previous contents intact
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let tmp_path = temp_path_for(path)?;
+    let written = (|| -> Result<()> {
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer_pretty(&mut out, value)?;
+        out.flush()?;
+        out.get_ref().sync_all()?;
+        rename(&tmp_path, path)?;
+        Ok(())
+    })();
+    if written.is_err() {
+        let _ = remove_file(&tmp_path);
+    }
+    written
+}
+
+/// A policy reify records in the artifact db's state directory, so the
+/// next reify can tell whether it changed
+pub trait RecordedPolicy: Serialize + DeserializeOwned {
+    /// the name of the file in the state directory
+    const FILE_NAME: &'static str;
+
+    /// the policy the last complete reify used, if it was recorded
+    fn recorded(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(Self::FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(read_json(&path)?))
+    }
+
+    /// record the policy as the one the artifact db was reified with
+    fn record(&self, dir: &Path) -> Result<()> {
+        write_json(&dir.join(Self::FILE_NAME), self)
+    }
+}
+
+/// read the records in a JSON Lines file, in order. Lines that don't
+/// parse are skipped, they're partial lines from a process that was killed
+pub fn read_json_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let reader = BufReader::new(File::open(path).with_context(|| format!("Opening {:?}", path))?);
+    let mut ret = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        match serde_json::from_str::<T>(&line) {
+            Ok(v) => ret.push(v),
+            Err(e) => warn!("Skipping bad line in {:?} {}", path, e),
+        }
+    }
+    Ok(ret)
+}
+
+/// A JSON Lines file that threads append records to
+#[derive(Debug)]
+pub struct JsonLinesAppender {
+    out: Mutex<File>,
+}
+
+impl JsonLinesAppender {
+    /// open the file for appending, creating it if needed
+    pub fn open(path: &Path) -> Result<JsonLinesAppender> {
+        let out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Opening {:?}", path))?;
+        Ok(JsonLinesAppender {
+            out: Mutex::new(out),
+        })
+    }
+
+    /// append the record as a line
+    pub fn append<T: Serialize>(&self, record: &T) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut out = self.out.lock().expect("Lock JSON Lines file");
+        // one write per line so lines from different threads don't interleave
+        out.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs::remove_file};
+
+    use super::*;
+
+    #[test]
+    fn append_and_read_json_lines() {
+        let path = temp_dir().join(format!("json_lines_{:08x}.jsonl", rand::random::<u32>()));
+        let out = JsonLinesAppender::open(&path).expect("open");
+        out.append(&("a", 1)).expect("append");
+        out.append(&("b", 2)).expect("append");
+        drop(out);
+        // a line cut short when a process was killed
+        let out = JsonLinesAppender::open(&path).expect("reopen");
+        out.out
+            .lock()
+            .expect("lock")
+            .write_all(b"[\"c\", ")
+            .expect("write");
+
+        let read: Vec<(String, u32)> = read_json_lines(&path).expect("read");
+        let _ = remove_file(&path);
+        assert_eq!(read, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+    }
+}
@@ -1,5 +1,23 @@
 pub mod args;
+pub mod audit;
+pub mod checkpoint;
+pub mod checksum;
+pub mod config;
+pub mod coordinate_filter;
+pub mod crawl_manifest;
+pub mod discovery;
+pub mod estimate;
+pub mod file_policy;
+pub mod globs;
 pub mod http_stuff;
+pub mod json_file;
+pub mod maven_version;
+pub mod metadata;
+pub mod negative_cache;
+pub mod plan_file;
 pub mod plan_merge;
+pub mod plan_output;
 pub mod response_data;
 pub mod run_state;
+pub mod snapshot;
+pub mod version_policy;
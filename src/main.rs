@@ -1,11 +1,14 @@
 use std::{thread::sleep, time::Duration};
 
 use anyhow::Result;
-use clap::Parser;
 use env_logger::Env;
-use log::info;
+use log::{info, warn};
 use mvn_crawler_carl::{
-    args::Args,
+    args::{Args, Cli, Mode},
+    audit::audit_and_report,
+    checkpoint::{CrawlCheckpoint, periodic_checkpoint, save_checkpoint},
+    crawl_manifest::{CrawlManifest, list_crawls_to_console},
+    estimate::estimate_to_console,
     http_stuff::{periodic_info, spawn_a_page},
     plan_merge::{do_merge, plan_merge_to_console},
     run_state::RunState,
@@ -18,27 +21,66 @@ fn main() -> Result<()> {
             .write_style_or("MY_LOG_STYLE", "always"),
     )
     .init();
-    let args = Args::parse();
-
-    let state = RunState::new(args.clone());
+    let cli = Cli::parse_command_line();
+    for (flag, command) in cli.deprecated_flags() {
+        warn!(
+            "`{}` is deprecated, use the `{}` command instead",
+            flag, command
+        );
+    }
 
-    // should we plan the merge
-    if state.plan() {
-        plan_merge_to_console(state.clone())?;
-        return Ok(());
+    let args = Args::try_from(cli)?;
+    if let Some(profile) = args.profile() {
+        info!("Using the settings of profile {}", profile);
     }
 
-    // should we do the real merge?
-    if state.reify_artifact_db() {
-        info!("Started updating artifact DB");
-        do_merge(state.clone())?;
-        return Ok(());
+    let state = RunState::new(args);
+
+    match state.mode() {
+        Mode::List => {
+            list_crawls_to_console(state.clone())?;
+            return Ok(());
+        }
+        // should we plan the merge
+        Mode::Plan => {
+            plan_merge_to_console(state.clone())?;
+            return Ok(());
+        }
+        Mode::Estimate => {
+            estimate_to_console(state.clone())?;
+            return Ok(());
+        }
+        // should we do the real merge?
+        Mode::Reify => {
+            info!("Started updating artifact DB");
+            do_merge(state.clone())?;
+            return Ok(());
+        }
+        Mode::Audit => {
+            audit_and_report(state.clone())?;
+            return Ok(());
+        }
+        Mode::Crawl => {}
     }
 
-    state.push_page(&state.repo_url()?);
+    match state.resume_dir() {
+        Some(dir) => {
+            let checkpoint = CrawlCheckpoint::load(&dir)?;
+            info!(
+                "Resuming crawl in {:?} from checkpoint at {}",
+                dir, checkpoint.saved_at
+            );
+            state.restore_checkpoint(checkpoint)?;
+        }
+        None => state.push_page(&state.repo_url()?),
+    }
+    if let Some(previous) = state.previous_crawl() {
+        info!("Reusing unchanged metadata from {:?}", previous);
+    }
     info!("Kicking off run");
     spawn_a_page(state.clone());
     periodic_info(state.clone());
+    let checkpointer = periodic_checkpoint(state.clone());
     while state.thread_cnt() > 0 {
         sleep(Duration::from_millis(200));
 
@@ -50,6 +92,12 @@ fn main() -> Result<()> {
         }
     }
 
+    if checkpointer.join().is_err() {
+        warn!("The checkpoint thread panicked");
+    }
+    save_checkpoint(state.clone())?;
+    // mark the crawl as complete so plan and reify will use it
+    CrawlManifest::from_state(state.clone())?.save(&state.crawl_db_dest_dir())?;
     info!("At {:?}, done with run {:?}", state.run_duration(), state);
 
     Ok(())
@@ -0,0 +1,505 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::{Error, Result, bail};
+
+/// the known qualifiers, in order. Unknown qualifiers sort after
+/// all of them, alphabetically
+const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+
+/// where a release (no qualifier) sorts among the qualifiers
+const RELEASE_INDEX: usize = 5;
+
+/// A piece of a parsed version
+#[derive(Debug, Clone)]
+enum Item {
+    /// the digits, without leading zeros, so any length compares
+    Int(String),
+    Str(String),
+    List(Vec<Item>),
+}
+
+/// a qualifier's sort key. Known qualifiers sort by position,
+/// unknown ones after them by name
+fn qualifier_key(q: &str) -> (usize, &str) {
+    match QUALIFIERS.iter().position(|k| *k == q) {
+        Some(i) => (i, ""),
+        None => (QUALIFIERS.len(), q),
+    }
+}
+
+fn compare_ints(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+impl Item {
+    fn int(digits: &str) -> Item {
+        Item::Int(digits.trim_start_matches('0').to_string())
+    }
+
+    /// a string item. Single letters followed by a digit are
+    /// shorthand, e.g., `1.0a1` is `1.0-alpha-1`
+    fn string(value: &str, followed_by_digit: bool) -> Item {
+        let value = match value {
+            "a" if followed_by_digit => "alpha",
+            "b" if followed_by_digit => "beta",
+            "m" if followed_by_digit => "milestone",
+            "ga" | "final" | "release" => "",
+            "cr" => "rc",
+            v => v,
+        };
+        Item::Str(value.to_string())
+    }
+
+    fn parse(is_digit: bool, buf: &str) -> Item {
+        if is_digit {
+            Item::int(buf)
+        } else {
+            Item::string(buf, false)
+        }
+    }
+
+    /// items that don't change the ordering when they're on the end, e.g., `1.0` is `1`
+    fn is_null(&self) -> bool {
+        match self {
+            Item::Int(v) => v.is_empty(),
+            Item::Str(v) => qualifier_key(v) == (RELEASE_INDEX, ""),
+            Item::List(items) => items.is_empty(),
+        }
+    }
+
+    /// compare with another item, `None` when the other version is shorter
+    fn compare(&self, other: Option<&Item>) -> Ordering {
+        match (self, other) {
+            (Item::Int(v), None) => {
+                if v.is_empty() {
+                    Ordering::Equal
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (Item::Int(a), Some(Item::Int(b))) => compare_ints(a, b),
+            (Item::Int(_), Some(_)) => Ordering::Greater,
+
+            (Item::Str(v), None) => qualifier_key(v).cmp(&(RELEASE_INDEX, "")),
+            (Item::Str(_), Some(Item::Int(_))) => Ordering::Less,
+            (Item::Str(a), Some(Item::Str(b))) => qualifier_key(a).cmp(&qualifier_key(b)),
+            (Item::Str(_), Some(Item::List(_))) => Ordering::Less,
+
+            // every item counts, not just the first, so `1-0.1` is after `1`
+            (Item::List(items), None) => items
+                .iter()
+                .map(|i| i.compare(None))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal),
+            (Item::List(_), Some(Item::Int(_))) => Ordering::Less,
+            (Item::List(_), Some(Item::Str(_))) => Ordering::Greater,
+            (Item::List(a), Some(Item::List(b))) => compare_lists(a, b),
+        }
+    }
+
+    /// does the version have a qualifier that sorts before a release?
+    fn is_pre_release(&self) -> bool {
+        match self {
+            Item::Int(_) => false,
+            Item::Str(v) => qualifier_key(v).0 < RELEASE_INDEX,
+            Item::List(items) => items.iter().any(|i| i.is_pre_release()),
+        }
+    }
+}
+
+fn compare_lists(a: &[Item], b: &[Item]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ord = match (a.get(i), b.get(i)) {
+            (None, None) => Ordering::Equal,
+            (None, Some(r)) => r.compare(None).reverse(),
+            (Some(l), r) => l.compare(r),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+/// drop trailing items that don't change the ordering. Like Maven,
+/// look past a trailing list that isn't empty, so `1.0-alpha` is `1-alpha`
+fn normalize(items: &mut Vec<Item>) {
+    let mut i = items.len();
+    while i > 0 {
+        i -= 1;
+        if items[i].is_null() {
+            items.remove(i);
+        } else if !matches!(items[i], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+/// A version, ordered the way Maven's `ComparableVersion` orders them:
+/// numbers compare as numbers, `1.0` equals `1`, and qualifiers sort as
+/// `alpha < beta < milestone < rc < snapshot < (release) < sp`
+#[derive(Debug, Clone)]
+pub struct MavenVersion {
+    value: String,
+    items: Vec<Item>,
+}
+
+impl MavenVersion {
+    pub fn parse(version: &str) -> MavenVersion {
+        let lower = version.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+
+        // a stack of nested lists. A `-` or a switch between digits and
+        // letters starts a new list inside the current one
+        let mut stack: Vec<Vec<Item>> = vec![vec![]];
+        let mut is_digit = false;
+        let mut start = 0;
+        let text = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+
+        for (i, c) in chars.iter().enumerate() {
+            let list = stack.last_mut().expect("Version list stack");
+            if *c == '.' {
+                if i == start {
+                    list.push(Item::int("0"));
+                } else {
+                    list.push(Item::parse(is_digit, &text(start, i)));
+                }
+                start = i + 1;
+            } else if *c == '-' {
+                if i == start {
+                    list.push(Item::int("0"));
+                } else {
+                    list.push(Item::parse(is_digit, &text(start, i)));
+                }
+                start = i + 1;
+                stack.push(vec![]);
+            } else if c.is_ascii_digit() {
+                if !is_digit && i > start {
+                    list.push(Item::string(&text(start, i), true));
+                    start = i;
+                    stack.push(vec![]);
+                }
+                is_digit = true;
+            } else {
+                if is_digit && i > start {
+                    list.push(Item::parse(true, &text(start, i)));
+                    start = i;
+                    stack.push(vec![]);
+                }
+                is_digit = false;
+            }
+        }
+        if chars.len() > start {
+            let item = Item::parse(is_digit, &text(start, chars.len()));
+            stack.last_mut().expect("Version list stack").push(item);
+        }
+
+        // close the nested lists, normalizing each as Maven does
+        while stack.len() > 1 {
+            let mut inner = stack.pop().expect("Version list stack");
+            normalize(&mut inner);
+            stack
+                .last_mut()
+                .expect("Version list stack")
+                .push(Item::List(inner));
+        }
+        let mut items = stack.pop().expect("Version list stack");
+        normalize(&mut items);
+        MavenVersion {
+            value: version.to_string(),
+            items,
+        }
+    }
+
+    /// the version as it was written
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// a snapshot or an alpha, beta, milestone or rc
+    pub fn is_pre_release(&self) -> bool {
+        self.items.iter().any(|i| i.is_pre_release())
+    }
+}
+
+impl fmt::Display for MavenVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Ord for MavenVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_lists(&self.items, &other.items)
+    }
+}
+
+impl PartialOrd for MavenVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MavenVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MavenVersion {}
+
+/// One end of a range
+#[derive(Debug, Clone)]
+struct Bound {
+    version: MavenVersion,
+    inclusive: bool,
+}
+
+/// A `[lower,upper)` piece of a range. A missing end is unbounded
+#[derive(Debug, Clone)]
+struct Restriction {
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+}
+
+impl Restriction {
+    fn contains(&self, v: &MavenVersion) -> bool {
+        let above = match &self.lower {
+            Some(b) if b.inclusive => *v >= b.version,
+            Some(b) => *v > b.version,
+            None => true,
+        };
+        let below = match &self.upper {
+            Some(b) if b.inclusive => *v <= b.version,
+            Some(b) => *v < b.version,
+            None => true,
+        };
+        above && below
+    }
+}
+
+/// A Maven version range like `[1.0,2.0)`, `[2.0,)`, `(,1.0],[1.2,)`,
+/// or `[1.5]`. A bare version, e.g., `1.5`, matches just that version
+#[derive(Debug, Clone)]
+pub struct VersionRange {
+    spec: String,
+    restrictions: Vec<Restriction>,
+}
+
+impl VersionRange {
+    pub fn contains(&self, v: &MavenVersion) -> bool {
+        self.restrictions.iter().any(|r| r.contains(v))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+}
+
+fn bound(text: &str, inclusive: bool) -> Option<Bound> {
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(Bound {
+            version: MavenVersion::parse(text),
+            inclusive,
+        })
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<VersionRange> {
+        let mut rest = spec.trim();
+        let mut restrictions = vec![];
+        if !rest.starts_with(['[', '(']) {
+            if rest.is_empty() || rest.contains([',', ']', ')']) {
+                bail!("Bad version range {:?}", spec);
+            }
+            let exact = bound(rest, true);
+            restrictions.push(Restriction {
+                lower: exact.clone(),
+                upper: exact,
+            });
+            rest = "";
+        }
+        while !rest.is_empty() {
+            let lower_inclusive = rest.starts_with('[');
+            let close = match rest.find([']', ')']) {
+                Some(i) => i,
+                None => bail!("Unclosed version range {:?}", spec),
+            };
+            let upper_inclusive = rest[close..].starts_with(']');
+            let inner = &rest[1..close];
+            let restriction = match inner.split_once(',') {
+                Some((lower, upper)) => {
+                    if upper.contains(',') {
+                        bail!("Too many versions in {:?}", spec);
+                    }
+                    Restriction {
+                        lower: bound(lower, lower_inclusive),
+                        upper: bound(upper, upper_inclusive),
+                    }
+                }
+                None => {
+                    // `[1.5]` is exactly 1.5
+                    if !(lower_inclusive && upper_inclusive) || inner.trim().is_empty() {
+                        bail!("Single versions need [], in {:?}", spec);
+                    }
+                    let exact = bound(inner, true);
+                    Restriction {
+                        lower: exact.clone(),
+                        upper: exact,
+                    }
+                }
+            };
+            if let (Some(l), Some(u)) = (&restriction.lower, &restriction.upper)
+                && l.version > u.version
+            {
+                bail!("Lower bound above upper bound in {:?}", spec);
+            }
+            restrictions.push(restriction);
+            rest = rest[close + 1..].trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after.trim_start();
+                if rest.is_empty() {
+                    bail!("Trailing comma in {:?}", spec);
+                }
+            } else if !rest.is_empty() {
+                bail!("Expected a comma between ranges in {:?}", spec);
+            }
+        }
+        Ok(VersionRange {
+            spec: spec.to_string(),
+            restrictions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> MavenVersion {
+        MavenVersion::parse(s)
+    }
+
+    fn range(s: &str) -> VersionRange {
+        s.parse().expect("a good range")
+    }
+
+    /// each version sorts strictly before the next
+    fn assert_ascending(versions: &[&str]) {
+        for pair in versions.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+            assert!(v(pair[1]) > v(pair[0]), "{} > {}", pair[1], pair[0]);
+        }
+    }
+
+    fn assert_same(a: &str, b: &str) {
+        assert_eq!(v(a), v(b), "{} == {}", a, b);
+    }
+
+    #[test]
+    fn qualifier_order() {
+        assert_ascending(&[
+            "1-alpha",
+            "1-beta",
+            "1-milestone",
+            "1-rc",
+            "1-snapshot",
+            "1",
+            "1-sp",
+            "1-abc",
+            "1-xyz",
+        ]);
+        assert_same("1-cr", "1-rc");
+        assert_same("1-ga", "1");
+        assert_same("1-final", "1");
+        assert_same("1-RELEASE", "1");
+        assert_same("1.0a1", "1.0-alpha-1");
+        assert_same("1.0b2", "1.0-beta-2");
+        assert_same("1.0m3", "1.0-milestone-3");
+        assert!(v("1.0-SNAPSHOT").is_pre_release());
+        assert!(v("2.0-rc1").is_pre_release());
+        assert!(!v("2.0").is_pre_release());
+        assert!(!v("2.0-sp1").is_pre_release());
+    }
+
+    #[test]
+    fn trailing_zeros() {
+        assert_same("1", "1.0");
+        assert_same("1", "1.0.0");
+        assert_same("1-0", "1");
+        assert_same("1.0-alpha", "1-alpha");
+        assert_same("1.010", "1.10");
+        assert_ascending(&["1", "1.0.1", "1.1", "1.9", "1.10", "1.12345678901234567890"]);
+    }
+
+    #[test]
+    fn sublists() {
+        assert_ascending(&["1", "1-0.1", "1-1", "1.1"]);
+        assert_ascending(&["1-alpha", "1-alpha-1", "1-alpha-2", "1-beta"]);
+        assert_ascending(&["1-1-snapshot", "1-1", "1-1-sp"]);
+        assert_same("1-1.0", "1-1");
+    }
+
+    #[test]
+    fn range_parsing() {
+        for bad in [
+            "",
+            "[1.0",
+            "(1.5)",
+            "[1.5)",
+            "[]",
+            "[2.0,1.0]",
+            "[1,2,3]",
+            "[1,2],",
+            "[1,2][3,4]",
+            "1,2",
+        ] {
+            assert!(
+                bad.parse::<VersionRange>().is_err(),
+                "{:?} should fail",
+                bad
+            );
+        }
+        assert_eq!(range(" [1.0,2.0) ").as_str(), " [1.0,2.0) ");
+    }
+
+    #[test]
+    fn range_containment() {
+        let r = range("[1.0,2.0)");
+        assert!(r.contains(&v("1")));
+        assert!(r.contains(&v("1.5")));
+        assert!(!r.contains(&v("2.0")));
+        assert!(r.contains(&v("2.0-rc1")));
+        assert!(!r.contains(&v("0.9")));
+
+        let r = range("[2.0,)");
+        assert!(r.contains(&v("2")));
+        assert!(r.contains(&v("300")));
+        assert!(!r.contains(&v("2.0-beta")));
+
+        let r = range("(,1.0],[1.2,)");
+        assert!(r.contains(&v("0.1")));
+        assert!(r.contains(&v("1.0")));
+        assert!(!r.contains(&v("1.1")));
+        assert!(r.contains(&v("1.2")));
+
+        let r = range("(1.0,1.2)");
+        assert!(!r.contains(&v("1.0")));
+        assert!(r.contains(&v("1.1")));
+        assert!(!r.contains(&v("1.2")));
+
+        for exact in ["[1.5]", "1.5"] {
+            let r = range(exact);
+            assert!(r.contains(&v("1.5")));
+            assert!(r.contains(&v("1.5.0")));
+            assert!(!r.contains(&v("1.5.1")));
+            assert!(!r.contains(&v("1.4")));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use xmltree::Element as XmlElement;
+
+use crate::plan_merge::version_from_metadata;
+
+/// The three kinds of `maven-metadata.xml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataKind {
+    /// in a group's directory, lists the group's plugins and their prefixes
+    Group,
+    /// in an artifact's directory, lists its versions
+    Artifact,
+    /// in a snapshot version's directory, lists the files of the latest build
+    Version,
+}
+
+/// What an artifact's `maven-metadata.xml` says about it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactMetadata {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub latest: Option<String>,
+    pub release: Option<String>,
+    pub versions: Vec<String>,
+    /// when the metadata was last deployed to, if it says
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// the trimmed text of the element's child, `None` if it's missing or blank
+pub fn child_text(e: &XmlElement, name: &str) -> Option<String> {
+    e.get_child(name)
+        .and_then(|c| c.get_text().map(|t| t.trim().to_string()))
+        .filter(|t| !t.is_empty())
+}
+
+/// parse a `<lastUpdated>` like `20250101120000`, which is in UTC
+pub fn parse_last_updated(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+/// work out which kind of metadata the file is. Artifact metadata
+/// must have the group, artifact, and at least one version
+pub fn metadata_kind(metadata: &[u8]) -> Result<MetadataKind> {
+    let md = XmlElement::parse(metadata)?;
+    let versioning = md.get_child("versioning");
+    // old artifact metadata can have a `<version>` too, so check for the list first
+    if versioning.and_then(|v| v.get_child("versions")).is_some() {
+        version_from_metadata(metadata)?;
+        return Ok(MetadataKind::Artifact);
+    }
+    if md.get_child("plugins").is_some() {
+        return Ok(MetadataKind::Group);
+    }
+    let has_snapshot = versioning.is_some_and(|v| {
+        v.get_child("snapshot").is_some() || v.get_child("snapshotVersions").is_some()
+    });
+    if md.get_child("version").is_some() || has_snapshot {
+        return Ok(MetadataKind::Version);
+    }
+    bail!("Not group, artifact, or version metadata")
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+
+use crate::json_file::{JsonLinesAppender, read_json_lines};
+
+pub const NOT_FOUND_FILE: &str = "not_found.jsonl";
+
+/// A line in the negative cache file. Later lines for
+/// the same path override earlier ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NotFoundRecord {
+    path: String,
+    at: DateTime<Utc>,
+    /// `false` when a path that was missing has since been found
+    #[serde(default = "default_missing")]
+    missing: bool,
+}
+
+fn default_missing() -> bool {
+    true
+}
+
+/// The artifact paths (group, artifact, version and suffix) that the repo
+/// answered with a 404, so the planner doesn't keep asking for `.war`s
+/// and `.ear`s that were never published
+#[derive(Debug)]
+pub struct NegativeCache {
+    entries: HashMap<String, DateTime<Utc>>,
+    ttl: Option<Duration>,
+    disabled: bool,
+    out: JsonLinesAppender,
+}
+
+impl NegativeCache {
+    /// load the cache from the directory, creating the file if
+    /// needed. Entries older than `ttl` are re-probed. If `disabled`,
+    /// nothing is treated as missing but 404s are still recorded
+    pub fn load(dir: &Path, ttl: Option<Duration>, disabled: bool) -> Result<NegativeCache> {
+        let path = dir.join(NOT_FOUND_FILE);
+        let mut entries = HashMap::new();
+        if path.is_file() {
+            for r in read_json_lines::<NotFoundRecord>(&path)? {
+                if r.missing {
+                    entries.insert(r.path, r.at);
+                } else {
+                    entries.remove(&r.path);
+                }
+            }
+        }
+        info!(
+            "Loaded {} known missing artifacts from {:?}",
+            entries.len().separate_with_commas(),
+            path
+        );
+        Ok(NegativeCache {
+            entries,
+            ttl,
+            disabled,
+            out: JsonLinesAppender::open(&path)?,
+        })
+    }
+
+    /// did the path 404 recently enough that it's not worth asking again?
+    pub fn is_known_missing(&self, path: &str) -> bool {
+        if self.disabled {
+            return false;
+        }
+        match (self.entries.get(path), self.ttl) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(at), Some(ttl)) => Utc::now() - *at < ttl,
+        }
+    }
+
+    /// was the path ever recorded as missing?
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// record that the path 404'd
+    pub fn record_missing(&self, path: &str) -> Result<()> {
+        self.append(path, true)
+    }
+
+    /// record that a path that used to 404 has been found
+    pub fn record_found(&self, path: &str) -> Result<()> {
+        self.append(path, false)
+    }
+
+    fn append(&self, path: &str, missing: bool) -> Result<()> {
+        self.out.append(&NotFoundRecord {
+            path: path.to_string(),
+            at: Utc::now(),
+            missing,
+        })
+    }
+}
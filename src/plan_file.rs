@@ -0,0 +1,314 @@
+use std::{
+    collections::HashSet,
+    fs::{File, remove_file, rename},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use flume::Sender;
+use log::info;
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+
+use crate::{
+    file_policy::FilePolicy,
+    json_file::{JsonLinesAppender, read_json_lines},
+    plan_merge::{MergeCmd, MergeEntry, MergeGroup, MergeOutcome, end_merge_workers},
+    response_data::temp_path_for,
+    run_state::State,
+    snapshot::SnapshotBuilds,
+};
+
+/// the plan written by `plan --save-plan`, in the crawl directory
+pub const PLAN_FILE: &str = "reify_plan.jsonl";
+
+/// the journal next to a plan file, e.g., `reify_plan.journal.jsonl`
+pub fn journal_path(plan: &Path) -> PathBuf {
+    plan.with_extension("journal.jsonl")
+}
+
+/// The first line of a plan: where it came from and the settings that
+/// decide which files its version directories expand to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanHeader {
+    /// the name of the crawl directory the plan was made from
+    crawl: String,
+    /// the file policy, with the `--discovery` mode
+    file_policy: FilePolicy,
+    snapshots: SnapshotBuilds,
+}
+
+impl PlanHeader {
+    /// make sure the reify expands and filters files the way the plan was made to
+    fn check_settings(&self, policy: &FilePolicy, snapshots: SnapshotBuilds) -> Result<()> {
+        if !self.file_policy.same_files_as(policy) {
+            bail!(
+                "The plan was made with the file policy {}, give reify the same `--discovery`, `--file-policy`, `--suffix`, `--include-file` and `--exclude-file`",
+                serde_json::to_string(&self.file_policy)?
+            );
+        }
+        if self.snapshots != snapshots {
+            bail!(
+                "The plan was made with `--snapshots {}`, give reify the same",
+                serde_json::to_string(&self.snapshots)?.trim_matches('"')
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `path` relative to `base`. Plans hold relative paths so they don't
+/// depend on the directory they were made in
+fn relative_to(path: &Path, base: &Path) -> Result<PathBuf> {
+    match path.strip_prefix(base) {
+        Ok(rel) => Ok(rel.to_path_buf()),
+        Err(_) => bail!("{:?} isn't in {:?}", path, base),
+    }
+}
+
+/// `rel` from a plan, joined to `base`. Anything that could point
+/// outside `base` is refused
+fn resolve(rel: &Path, base: &Path) -> Result<PathBuf> {
+    if !rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        bail!("{:?} in the plan isn't a path inside {:?}", rel, base);
+    }
+    Ok(base.join(rel))
+}
+
+/// Writes a plan as a `MergeGroup` per line, after a header naming
+/// the crawl, so it can be reviewed and lines dropped before reifying from it
+pub struct PlanWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    crawl: PathBuf,
+    artifact_db: PathBuf,
+    out: BufWriter<File>,
+    groups: usize,
+}
+
+impl PlanWriter {
+    /// start the plan of reifying `crawl` into `artifact_db` under the file policy
+    /// and snapshot setting. It's written next to `path` and only replaces it when finished
+    pub fn create(
+        path: &Path,
+        crawl: &Path,
+        artifact_db: &Path,
+        file_policy: &FilePolicy,
+        snapshots: SnapshotBuilds,
+    ) -> Result<PlanWriter> {
+        let name = match crawl.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => bail!("Couldn't get the name of the crawl {:?}", crawl),
+        };
+        let tmp_path = temp_path_for(path)?;
+        let out = File::create(&tmp_path).with_context(|| format!("Creating {:?}", tmp_path))?;
+        let mut out = BufWriter::new(out);
+        let header = PlanHeader {
+            crawl: name,
+            file_policy: file_policy.clone(),
+            snapshots,
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        writeln!(out)?;
+        Ok(PlanWriter {
+            path: path.to_path_buf(),
+            tmp_path,
+            crawl: crawl.to_path_buf(),
+            artifact_db: artifact_db.to_path_buf(),
+            out,
+            groups: 0,
+        })
+    }
+
+    /// write the group with its paths relative to the crawl and the artifact db
+    pub fn write_group(&mut self, group: &MergeGroup) -> Result<()> {
+        let mut group = group.clone();
+        for entry in group.entries_mut() {
+            entry.dest_file = relative_to(&entry.dest_file, &self.artifact_db)?;
+            if let Some(source) = &entry.source_file {
+                entry.source_file = Some(relative_to(source, &self.crawl)?);
+            }
+        }
+        serde_json::to_writer(&mut self.out, &group)?;
+        writeln!(self.out)?;
+        self.groups += 1;
+        Ok(())
+    }
+
+    /// put the plan in place. The journal of the plan it replaces
+    /// is removed, it's for different work
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        self.out.get_ref().sync_all()?;
+        rename(&self.tmp_path, &self.path)?;
+        let journal = journal_path(&self.path);
+        if journal.is_file() {
+            remove_file(&journal)?;
+        }
+        info!(
+            "Saved the plan for {} packages to {:?}",
+            self.groups.separate_with_commas(),
+            self.path
+        );
+        Ok(())
+    }
+}
+
+/// A line in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    /// relative to the artifact db, like the plan
+    dest_file: PathBuf,
+    outcome: MergeOutcome,
+}
+
+/// The plan entries that are done, so reifying from an
+/// interrupted plan picks up where it left off
+#[derive(Debug)]
+pub struct PlanJournal {
+    out: JsonLinesAppender,
+    artifact_db: PathBuf,
+}
+
+impl PlanJournal {
+    /// the destinations, relative to the artifact db, of the entries in the journal
+    pub fn done(path: &Path) -> Result<HashSet<PathBuf>> {
+        if !path.is_file() {
+            return Ok(HashSet::new());
+        }
+        Ok(read_json_lines::<JournalEntry>(path)?
+            .into_iter()
+            .map(|e| e.dest_file)
+            .collect())
+    }
+
+    pub fn open(path: &Path, artifact_db: &Path) -> Result<PlanJournal> {
+        Ok(PlanJournal {
+            out: JsonLinesAppender::open(path)?,
+            artifact_db: artifact_db.to_path_buf(),
+        })
+    }
+
+    /// record that the entry is done
+    pub fn record(&self, entry: &MergeEntry, outcome: &MergeOutcome) -> Result<()> {
+        self.out.append(&JournalEntry {
+            dest_file: relative_to(&entry.dest_file, &self.artifact_db)?,
+            outcome: outcome.clone(),
+        })
+    }
+}
+
+/// send the merge workers the groups in the plan file, without
+/// the entries the journal says are done
+pub fn reify_from_plan(dest: Sender<MergeCmd>, state: State) -> Result<()> {
+    let plan = state.plan_file()?;
+    let artifact_db = state.artifact_db()?;
+    let reader =
+        BufReader::new(File::open(&plan).with_context(|| format!("Opening plan {:?}", plan))?);
+    let mut lines = reader.lines();
+    let header: PlanHeader = match lines.next() {
+        Some(line) => serde_json::from_str(&line?).with_context(|| {
+            format!(
+                "{:?} doesn't start with the crawl and settings it was made with, save the plan again",
+                plan
+            )
+        })?,
+        None => bail!("The plan {:?} is empty", plan),
+    };
+    let crawl = state.plan_crawl(&header.crawl)?;
+    header.check_settings(state.file_policy()?, state.snapshots())?;
+
+    let journal = journal_path(&plan);
+    let done = PlanJournal::done(&journal)?;
+    state.start_plan_journal(PlanJournal::open(&journal, &artifact_db)?)?;
+    info!(
+        "Reifying from {:?} of crawl {:?}, {} entries already done",
+        plan,
+        crawl,
+        done.len().separate_with_commas()
+    );
+
+    let mut sent = 0usize;
+    let mut skipped = 0usize;
+    for (line_no, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the header was line 1
+        let context = || format!("Line {} of {:?}", line_no + 2, plan);
+        let mut group: MergeGroup = serde_json::from_str(&line).with_context(context)?;
+        group.retain_entries(|e| !done.contains(&e.dest_file));
+        if group.entries().is_empty() {
+            skipped += 1;
+            continue;
+        }
+        for entry in group.entries_mut() {
+            entry.dest_file = resolve(&entry.dest_file, &artifact_db).with_context(context)?;
+            if let Some(source) = &entry.source_file {
+                entry.source_file = Some(resolve(source, &crawl).with_context(context)?);
+            }
+        }
+        dest.send(MergeCmd::Merge(group))?;
+        sent += 1;
+    }
+    info!(
+        "Sent {} packages from the plan, {} were already done",
+        sent.separate_with_commas(),
+        skipped.separate_with_commas()
+    );
+
+    end_merge_workers(&dest, &state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs::read_to_string};
+
+    use super::*;
+    use crate::discovery::ArtifactDiscovery;
+
+    #[test]
+    fn header_round_trip() {
+        let dir = temp_dir().join(format!("plan_header_{:08x}", rand::random::<u32>()));
+        let path = dir.join(PLAN_FILE);
+        let mut policy = FilePolicy::default();
+        policy.include = vec!["*.pom".to_string()];
+        policy.discovery = ArtifactDiscovery::Listing;
+        let writer = PlanWriter::create(
+            &path,
+            Path::new("crawls/2025_04_13_14_43_04_crawl_db"),
+            Path::new("artifacts"),
+            &policy,
+            SnapshotBuilds::All,
+        )
+        .expect("create the plan");
+        writer.finish().expect("finish the plan");
+        let text = read_to_string(&path).expect("read the plan");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let header: PlanHeader =
+            serde_json::from_str(text.lines().next().expect("a header")).expect("parse");
+        assert_eq!(header.crawl, "2025_04_13_14_43_04_crawl_db");
+        assert!(header.file_policy.same_files_as(&policy));
+        assert_eq!(header.snapshots, SnapshotBuilds::All);
+        assert!(header.check_settings(&policy, SnapshotBuilds::All).is_ok());
+
+        // a reify with other settings would expand the directories differently
+        assert!(
+            header
+                .check_settings(&FilePolicy::default(), SnapshotBuilds::All)
+                .is_err()
+        );
+        assert!(
+            header
+                .check_settings(&policy, SnapshotBuilds::Latest)
+                .is_err()
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use std::{
     collections::HashSet,
-    fs::{File, create_dir_all},
-    io::{Read, Write},
-    path::PathBuf,
+    fs::{File, read},
+    io::{Read, stdout},
+    path::{Path, PathBuf},
     thread::{self, sleep},
     time::{Duration, Instant},
 };
@@ -10,54 +10,62 @@ use std::{
 use anyhow::{Result, bail};
 
 use flume::{Receiver, Sender};
-use log::{error, info};
+use log::{error, info, warn};
 use rand::{rng, seq::SliceRandom};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
 use walkdir::WalkDir;
 use xmltree::Element as XmlElement;
 
 use crate::{
-    http_stuff::{build_client, get_subbed_url, periodic_info},
-    response_data::GOLD_FILE,
+    discovery::{
+        ArtifactDiscovery, artifact_and_version, list_version_dir, packaging_from_pom,
+        suffixes_for_packaging,
+    },
+    file_policy::FilePolicy,
+    http_stuff::{build_client, get_verified_url, is_not_found, periodic_info},
+    json_file::{RecordedPolicy, write_json},
+    metadata::{ArtifactMetadata, MetadataKind, child_text, metadata_kind, parse_last_updated},
+    plan_file::{PlanWriter, reify_from_plan},
+    plan_output::PlanPrinter,
+    response_data::{GOLD_FILE, write_atomically},
     run_state::State,
+    snapshot::{SnapshotBuilds, is_snapshot, snapshot_file_names},
+    version_policy::VersionPolicy,
 };
 
 pub fn base_path_from_group_and_artifact(group_id: &str, artifact_id: &str) -> String {
     format!("{}/{}/", group_id.replace(".", "/"), artifact_id,)
 }
-pub fn version_from_metadata(metadata: &Vec<u8>) -> Result<(String, String, Vec<String>)> {
-    let md = XmlElement::parse(metadata.as_slice())?;
-
-    // if let Some(md) = xml.get_child("metadata") {
-    let group_id: Option<String> = md
-        .get_child("groupId")
-        .and_then(|e| e.get_text().map(|t| t.to_string()));
-    let artifact_id = md
-        .get_child("artifactId")
-        .and_then(|e| e.get_text().map(|t| t.to_string()));
-    let versions: Option<Vec<String>> = md
-        .get_child("versioning")
-        .and_then(|e| e.get_child("versions"))
-        .map(|e| {
-            e.children
-                .iter()
-                .flat_map(|n| n.as_element())
-                .filter(|e| e.name == "version")
-                .flat_map(|e| e.get_text().map(|t| t.to_string()))
-                .collect()
-        });
+/// parse an artifact's `maven-metadata.xml`, which must have
+/// the group, artifact, and at least one version
+pub fn version_from_metadata(metadata: &[u8]) -> Result<ArtifactMetadata> {
+    let md = XmlElement::parse(metadata)?;
+    let group_id = child_text(&md, "groupId");
+    let artifact_id = child_text(&md, "artifactId");
+    let versioning = md.get_child("versioning");
+    let versions: Option<Vec<String>> = versioning.and_then(|e| e.get_child("versions")).map(|e| {
+        e.children
+            .iter()
+            .flat_map(|n| n.as_element())
+            .filter(|e| e.name == "version")
+            .flat_map(|e| e.get_text().map(|t| t.to_string()))
+            .collect()
+    });
 
     match (group_id, artifact_id, versions) {
-        (Some(group), Some(artifact), Some(vers)) if vers.len() > 0 => {
-            let base_path = base_path_from_group_and_artifact(&group, &artifact);
-            let mut ret = vec![];
-            for v in vers {
-                for s in suffixes() {
-                    let url = format!("{}{}/{}-{}{}", base_path, v, artifact, v, s);
-                    ret.push(url);
-                }
-            }
-
-            Ok((group, artifact, ret))
+        (Some(group_id), Some(artifact_id), Some(versions)) if !versions.is_empty() => {
+            Ok(ArtifactMetadata {
+                group_id,
+                artifact_id,
+                latest: versioning.and_then(|v| child_text(v, "latest")),
+                release: versioning.and_then(|v| child_text(v, "release")),
+                versions,
+                last_updated: versioning
+                    .and_then(|v| child_text(v, "lastUpdated"))
+                    .and_then(|t| parse_last_updated(&t)),
+            })
         }
         (group_id, artifact_id, versions) => {
             bail!(
@@ -69,6 +77,74 @@ pub fn version_from_metadata(metadata: &Vec<u8>) -> Result<(String, String, Vec<
         }
     }
 }
+
+/// the paths of the artifacts (or, for discovery modes that look at
+/// what's actually published, the version directories) to fetch
+pub fn planned_paths(
+    group: &str,
+    artifact: &str,
+    versions: &[String],
+    discovery: ArtifactDiscovery,
+    policy: &FilePolicy,
+) -> Vec<String> {
+    let base_path = base_path_from_group_and_artifact(group, artifact);
+    let mut ret = vec![];
+    for v in versions {
+        match discovery {
+            ArtifactDiscovery::Suffixes => {
+                for s in &policy.suffixes {
+                    let url = format!("{}{}/{}-{}{}", base_path, v, artifact, v, s);
+                    if policy.allows(&url) {
+                        ret.push(url);
+                    }
+                }
+            }
+            ArtifactDiscovery::Listing | ArtifactDiscovery::Pom => {
+                ret.push(format!("{}{}/", base_path, v));
+            }
+        }
+    }
+    ret
+}
+
+/// the paths of a snapshot version's files. The version's own metadata lists
+/// the timestamped files of the latest build; without it, fall back to
+/// the usual discovery
+pub fn snapshot_paths(
+    group: &str,
+    artifact: &str,
+    version: &str,
+    version_metadata: Option<&[u8]>,
+    builds: SnapshotBuilds,
+    discovery: ArtifactDiscovery,
+    policy: &FilePolicy,
+) -> Vec<String> {
+    let version_dir = format!(
+        "{}{}/",
+        base_path_from_group_and_artifact(group, artifact),
+        version
+    );
+    if builds == SnapshotBuilds::All {
+        return vec![version_dir];
+    }
+    let names = version_metadata
+        .and_then(|md| snapshot_file_names(md, artifact, version, &policy.suffixes).ok());
+    match names {
+        Some(names) => names
+            .into_iter()
+            .map(|n| format!("{}{}", version_dir, n))
+            .filter(|p| policy.allows(p))
+            .collect(),
+        None => planned_paths(
+            group,
+            artifact,
+            std::slice::from_ref(&version.to_string()),
+            discovery,
+            policy,
+        ),
+    }
+}
+
 pub fn suffixes() -> Vec<&'static str> {
     vec![
         ".jar",
@@ -80,29 +156,433 @@ pub fn suffixes() -> Vec<&'static str> {
     ]
 }
 
+pub const PLAN_ERRORS_FILE: &str = "plan_errors.json";
+
+/// A crawl metadata file the plan skipped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl PlanError {
+    fn new(path: &Path, error: impl std::fmt::Display) -> PlanError {
+        PlanError {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        }
+    }
+}
+
 /// a command sent from the planner to the targets
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MergeCmd {
     End,
     Merge(MergeGroup),
 }
 
-#[derive(Debug, Clone)]
+/// Why a package is being merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanReason {
+    /// the artifact db doesn't have the package's metadata yet
+    New,
+    /// the crawl's metadata differs from the artifact db's
+    Changed,
+    /// the metadata is the same, but files are missing
+    Backfill,
+    /// the audit found the files damaged or missing
+    Repair,
+}
+
+impl PlanReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanReason::New => "new",
+            PlanReason::Changed => "changed",
+            PlanReason::Backfill => "backfill",
+            PlanReason::Repair => "repair",
+        }
+    }
+}
+
+/// The files of one package, committed together by copying its metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MergeGroup {
     entries: Vec<MergeEntry>,
     group_id: String,
-    artifact_id: String,
+    /// `None` for a group's own (plugin) metadata
+    artifact_id: Option<String>,
+    reason: PlanReason,
+}
+
+impl MergeGroup {
+    pub fn new(
+        entries: Vec<MergeEntry>,
+        group_id: &str,
+        artifact_id: &str,
+        reason: PlanReason,
+    ) -> MergeGroup {
+        MergeGroup {
+            entries,
+            group_id: group_id.to_string(),
+            artifact_id: Some(artifact_id.to_string()),
+            reason,
+        }
+    }
+
+    /// the group's own metadata, which sits above its artifacts
+    pub fn for_group_metadata(
+        entries: Vec<MergeEntry>,
+        group_id: &str,
+        reason: PlanReason,
+    ) -> MergeGroup {
+        MergeGroup {
+            entries,
+            group_id: group_id.to_string(),
+            artifact_id: None,
+            reason,
+        }
+    }
+
+    pub fn entries(&self) -> &[MergeEntry] {
+        &self.entries
+    }
+
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    /// the artifact, `None` if the group is a group's own metadata
+    pub fn artifact_id(&self) -> Option<&str> {
+        self.artifact_id.as_deref()
+    }
+
+    /// `group:artifact`, or just the group for the group's own metadata
+    pub fn package(&self) -> String {
+        match &self.artifact_id {
+            Some(artifact_id) => format!("{}:{}", self.group_id, artifact_id),
+            None => self.group_id.clone(),
+        }
+    }
+
+    pub fn reason(&self) -> PlanReason {
+        self.reason
+    }
+
+    pub fn entries_mut(&mut self) -> &mut [MergeEntry] {
+        &mut self.entries
+    }
+
+    /// keep only the entries `keep` returns true for
+    pub fn retain_entries(&mut self, keep: impl FnMut(&MergeEntry) -> bool) {
+        self.entries.retain(keep);
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MergeEntry {
     pub source_url: Option<String>,
     pub source_file: Option<PathBuf>,
     pub dest_file: PathBuf,
-    pub state: State,
+    /// `source_url` is a version directory and the worker works
+    /// out which files in it to fetch
+    pub expand: bool,
+}
+
+/// what happened to a `MergeEntry`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeOutcome {
+    /// fetched (or copied) and written to the artifact db
+    Written,
+    /// the repo doesn't have it, e.g., a `.war` for a library
+    NotFound,
+    /// anything else. The group isn't committed so the next reify retries it
+    Failed(String),
+}
+
+/// fetch the entry's URL, verify it, and write it and its checksum files
+fn download_entry(
+    source_url: &str,
+    dest_file: &Path,
+    client: &mut Client,
+    state: State,
+) -> MergeOutcome {
+    let url = match state.repo_url() {
+        Ok(repo_url) => format!("{}/{}", repo_url, source_url),
+        Err(e) => return MergeOutcome::Failed(e.to_string()),
+    };
+    let (loaded, checksums) = match get_verified_url(&url, client, state.clone()) {
+        Ok(v) => v,
+        Err(e) if is_not_found(&e) => return MergeOutcome::NotFound,
+        Err(e) => return MergeOutcome::Failed(e.to_string()),
+    };
+
+    let written = write_atomically(dest_file, loaded.data()).and_then(|_| {
+        // keep the checksum files next to the artifact
+        for checksum in checksums {
+            let mut name = dest_file.as_os_str().to_owned();
+            name.push(checksum.algorithm.extension());
+            write_atomically(&PathBuf::from(name), &checksum.raw)?;
+        }
+        Ok(())
+    });
+    match written {
+        Ok(_) => MergeOutcome::Written,
+        Err(e) => MergeOutcome::Failed(format!("Writing {:?} {}", dest_file, e)),
+    }
+}
+
+/// copy a file from the crawl into the artifact db
+fn copy_entry(source_file: &Path, dest_file: &Path) -> Result<()> {
+    let mut bytes = vec![];
+    let mut in_file = File::open(source_file)?;
+    in_file.read_to_end(&mut bytes)?;
+    write_atomically(dest_file, &bytes)
+}
+
+/// update the counters and the negative cache for the entry's outcome
+fn record_outcome(entry: &MergeEntry, outcome: &MergeOutcome, state: State) -> Result<()> {
+    let source_url = entry.source_url.as_deref().unwrap_or_default();
+    let negative_cache = state.negative_cache()?;
+    match outcome {
+        MergeOutcome::Written => {
+            if negative_cache.contains(source_url) {
+                negative_cache.record_found(source_url)?;
+            }
+        }
+        MergeOutcome::NotFound => {
+            state.inc_not_found_cnt();
+            negative_cache.record_missing(source_url)?;
+        }
+        MergeOutcome::Failed(why) => {
+            state.inc_failed_download_cnt();
+            error!("Failed to fetch {} {}", source_url, why);
+        }
+    }
+    Ok(())
+}
+
+/// note the entry as done in the journal of the plan being reified, if any.
+/// Failures aren't noted so they're tried again when the reify is resumed
+fn journal_outcome(entry: &MergeEntry, outcome: &MergeOutcome, state: &State) -> Result<()> {
+    match (state.plan_journal(), outcome) {
+        (Some(_), MergeOutcome::Failed(_)) | (None, _) => Ok(()),
+        (Some(journal), outcome) => journal.record(entry, outcome),
+    }
+}
+
+/// download a single file entry and record what happened
+fn fetch_entry(entry: &MergeEntry, client: &mut Client, state: State) -> Result<MergeOutcome> {
+    let outcome = match entry {
+        MergeEntry {
+            source_url: Some(source_url),
+            source_file: None,
+            dest_file,
+            ..
+        } => download_entry(source_url, dest_file, client, state.clone()),
+        me => bail!("Got weird merge entry {:?}", me),
+    };
+    record_outcome(entry, &outcome, state)?;
+    Ok(outcome)
+}
+
+/// an entry to fetch `url` into the artifact db
+pub fn file_entry(url: String, state: State) -> Result<MergeEntry> {
+    Ok(MergeEntry {
+        dest_file: state.artifact_db()?.join(&url),
+        source_url: Some(url),
+        source_file: None,
+        expand: false,
+    })
+}
+
+/// fetch the version's POM into the artifact db, unless it's already
+/// there, and read it. The POM's outcome is added to `done`
+fn fetch_pom(
+    pom_path: &str,
+    client: &mut Client,
+    state: State,
+    done: &mut Vec<(MergeEntry, MergeOutcome)>,
+) -> Result<Option<Vec<u8>>> {
+    let pom_entry = file_entry(pom_path.to_string(), state.clone())?;
+    if !pom_entry.dest_file.is_file() {
+        let outcome = fetch_entry(&pom_entry, client, state)?;
+        let written = outcome == MergeOutcome::Written;
+        done.push((pom_entry.clone(), outcome));
+        if !written {
+            return Ok(None);
+        }
+    }
+    let mut pom = vec![];
+    File::open(&pom_entry.dest_file)?.read_to_end(&mut pom)?;
+    Ok(Some(pom))
+}
+
+/// the files besides the POM a version should have, from the packaging in
+/// its POM. `prefix` is the path up to the suffix, e.g., `org/example/foo/1.0/foo-1.0`
+fn files_for_pom(prefix: &str, pom: Option<&[u8]>, state: &State) -> Result<Vec<String>> {
+    let suffixes = match pom {
+        Some(pom) => match packaging_from_pom(pom) {
+            Ok(packaging) => suffixes_for_packaging(&packaging),
+            Err(e) => {
+                info!("Couldn't read the packaging from {}, {}", prefix, e);
+                suffixes_for_packaging("jar")
+            }
+        },
+        // no POM to go on, so fall back to guessing
+        None => state
+            .file_policy()?
+            .suffixes
+            .iter()
+            .filter(|s| *s != ".pom")
+            .cloned()
+            .collect(),
+    };
+    Ok(suffixes
+        .into_iter()
+        .map(|s| format!("{}{}", prefix, s))
+        .collect())
+}
+
+/// how the files in a version directory are found. Every build of a
+/// snapshot is only known from the listing
+fn expand_discovery(dir: &str, state: &State) -> ArtifactDiscovery {
+    if state.snapshots() == SnapshotBuilds::All
+        && artifact_and_version(dir).is_some_and(|(_, v)| is_snapshot(&v))
+    {
+        ArtifactDiscovery::Listing
+    } else {
+        state.discovery()
+    }
+}
+
+/// the files in a version directory that the file policy allows, found
+/// from the listing or from the POM and the files its packaging produces.
+/// `pom` gets the POM's bytes from its path, `None` if there's no POM. The
+/// POM itself isn't in the list
+pub fn version_dir_files(
+    dir: &str,
+    client: &mut Client,
+    state: &State,
+    pom: impl FnOnce(&str, &mut Client) -> Result<Option<Vec<u8>>>,
+) -> Result<Vec<String>> {
+    let files = match expand_discovery(dir, state) {
+        ArtifactDiscovery::Suffixes => bail!("Can't expand {} when guessing suffixes", dir),
+        ArtifactDiscovery::Listing => list_version_dir(dir, client, state.clone())?,
+        ArtifactDiscovery::Pom => {
+            let (artifact, version) = match artifact_and_version(dir) {
+                Some(v) => v,
+                None => bail!("Can't get the artifact and version from {}", dir),
+            };
+            let prefix = format!("{}{}-{}", dir, artifact, version);
+            let pom = pom(&format!("{}.pom", prefix), client)?;
+            files_for_pom(&prefix, pom.as_deref(), state)?
+        }
+    };
+    let policy = state.file_policy()?;
+    Ok(files.into_iter().filter(|f| policy.allows(f)).collect())
+}
+
+/// turn a version directory entry into an entry for each file to fetch.
+/// Files fetched while working that out (e.g., the POM) are added to `done`
+fn expand_entry(
+    entry: &MergeEntry,
+    client: &mut Client,
+    state: State,
+    done: &mut Vec<(MergeEntry, MergeOutcome)>,
+) -> std::result::Result<Vec<MergeEntry>, MergeOutcome> {
+    let dir = entry.source_url.as_deref().unwrap_or_default();
+    let files = version_dir_files(dir, client, &state, |pom_path, client| {
+        fetch_pom(pom_path, client, state.clone(), done)
+    });
+    match files {
+        Ok(files) => files
+            .into_iter()
+            .map(|url| file_entry(url, state.clone()))
+            .collect::<Result<Vec<MergeEntry>>>()
+            .map_err(|e| MergeOutcome::Failed(e.to_string())),
+        Err(e) if is_not_found(&e) => Err(MergeOutcome::NotFound),
+        Err(e) => Err(MergeOutcome::Failed(e.to_string())),
+    }
+}
+
+/// download everything in the group and, if nothing failed, commit it by
+/// copying the metadata file. Returns the outcome of each entry
+fn merge_group(
+    merge_grp: &MergeGroup,
+    client: &mut Client,
+    state: State,
+) -> Result<Vec<(MergeEntry, MergeOutcome)>> {
+    // the metadata file is the "transaction commit," so it's only
+    // copied once every artifact in the group has been written
+    let (commits, downloads): (Vec<&MergeEntry>, Vec<&MergeEntry>) = merge_grp
+        .entries
+        .iter()
+        .partition(|e| e.source_file.is_some());
+
+    let mut ret = vec![];
+    for to_process in downloads {
+        if to_process.expand {
+            match expand_entry(to_process, client, state.clone(), &mut ret) {
+                Ok(files) => {
+                    let mut all_fetched = true;
+                    // only happens when backfilling, new versions have nothing on disk
+                    for file in files.iter().filter(|f| !f.dest_file.is_file()) {
+                        let outcome = fetch_entry(file, client, state.clone())?;
+                        all_fetched &= !matches!(outcome, MergeOutcome::Failed(_));
+                        ret.push((file.clone(), outcome));
+                    }
+                    if all_fetched {
+                        journal_outcome(to_process, &MergeOutcome::Written, &state)?;
+                    }
+                }
+                Err(outcome) => {
+                    record_outcome(to_process, &outcome, state.clone())?;
+                    journal_outcome(to_process, &outcome, &state)?;
+                    ret.push((to_process.clone(), outcome));
+                }
+            }
+        } else {
+            let outcome = fetch_entry(to_process, client, state.clone())?;
+            journal_outcome(to_process, &outcome, &state)?;
+            ret.push((to_process.clone(), outcome));
+        }
+    }
+
+    let failed = ret
+        .iter()
+        .filter(|(_, o)| matches!(o, MergeOutcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        state.inc_uncommitted_cnt();
+        error!(
+            "Not committing {}, {} of {} downloads failed",
+            merge_grp.package(),
+            failed,
+            ret.len()
+        );
+        return Ok(ret);
+    }
+
+    for to_process in commits {
+        match to_process {
+            MergeEntry {
+                source_url: None,
+                source_file: Some(source_file),
+                dest_file,
+                ..
+            } => copy_entry(source_file, dest_file)?,
+            me => bail!("Got weird merge entry {:?}", me),
+        }
+        journal_outcome(to_process, &MergeOutcome::Written, &state)?;
+        ret.push((to_process.clone(), MergeOutcome::Written));
+    }
+    Ok(ret)
 }
 
-fn read_stream_and_do_merge(rx: Receiver<MergeCmd>, _state: State) -> Result<()> {
+fn read_stream_and_do_merge(rx: Receiver<MergeCmd>, state: State) -> Result<()> {
     let mut client = build_client();
 
     let mut loop_cnt = 0;
@@ -112,60 +592,16 @@ fn read_stream_and_do_merge(rx: Receiver<MergeCmd>, _state: State) -> Result<()>
         match &merge_cmd {
             MergeCmd::End => return Ok(()),
             MergeCmd::Merge(merge_grp) => {
-                for to_process in &merge_grp.entries {
-                    match to_process {
-                        MergeEntry {
-                            source_url: Some(source_url),
-                            source_file: None,
-                            dest_file,
-                            state,
-                        } => {
-                            let url = format!("{}/{}", state.repo_url()?, source_url);
-                            match get_subbed_url(&url, &mut client, state.clone()) {
-                                Ok(loaded) => {
-                                    create_dir_all(match dest_file.parent() {
-                                        Some(f) => f,
-                                        None => bail!(
-                                            "Couldn't get parent directory for {:?}",
-                                            dest_file
-                                        ),
-                                    })?;
-                                    let mut out_file = File::create(&dest_file)?;
-                                    out_file.write_all(&loaded.data())?;
-                                }
-                                Err(_) => {
-                                    // log ?? dunno
-                                }
-                            }
-                        }
-                        MergeEntry {
-                            source_url: None,
-                            source_file: Some(source_file),
-                            dest_file,
-                            state: _,
-                        } => {
-                            let mut bytes = vec![];
-                            let mut in_file = File::open(&source_file)?;
-                            in_file.read_to_end(&mut bytes)?;
-                            create_dir_all(match dest_file.parent() {
-                                Some(f) => f,
-                                None => bail!("Couldn't get parent directory for {:?}", dest_file),
-                            })?;
-                            let mut out_file = File::create(&dest_file)?;
-                            out_file.write_all(&bytes)?;
-                        }
-                        me => {
-                            bail!("Got weird merge entry {:?}", me);
-                        }
-                    }
+                if let Err(e) = merge_group(merge_grp, &mut client, state.clone()) {
+                    state.inc_uncommitted_cnt();
+                    error!("Failed to merge {} {:?}", merge_grp.package(), e);
                 }
 
                 loop_cnt += 1;
                 if loop_cnt % 50000 == 0 || merge_grp.entries.len() > 2000 {
                     info!(
-                        "Done {}/{} cnt {}, took {:?}",
-                        merge_grp.group_id,
-                        merge_grp.artifact_id,
+                        "Done {} cnt {}, took {:?}",
+                        merge_grp.package(),
                         merge_grp.entries.len(),
                         Instant::now().duration_since(start)
                     );
@@ -176,14 +612,17 @@ fn read_stream_and_do_merge(rx: Receiver<MergeCmd>, _state: State) -> Result<()>
     Ok(())
 }
 
-pub fn do_merge(state: State) -> Result<()> {
+/// start the workers that do the `MergeCmd`s sent to the returned sender
+pub fn spawn_merge_workers(state: State) -> Sender<MergeCmd> {
     let (tx, rx) = flume::bounded(30);
 
     for x in 0..state.max_threads() {
         let rx_clone = rx.clone();
         let state_clone = state.clone();
+        // increment the running thread before spawning so `plan_merge`
+        // doesn't see zero threads and return before the work is done
+        state_clone.inc_running_threads();
         thread::spawn(move || {
-            state_clone.inc_running_threads();
             match read_stream_and_do_merge(rx_clone, state_clone.clone()) {
                 Ok(_) => info!("Thread {} terminated normally", x),
                 Err(e) => error!("Thread {} terminated abnormally {:?}", x, e),
@@ -192,37 +631,127 @@ pub fn do_merge(state: State) -> Result<()> {
         });
     }
     drop(rx);
+    tx
+}
 
+/// tell the workers to end and wait for them
+pub fn end_merge_workers(dest: &Sender<MergeCmd>, state: &State) -> Result<()> {
+    for _ in 0..state.thread_cnt() + 5 {
+        dest.send(MergeCmd::End)?;
+    }
+
+    // wait for the threads to end before returning and
+    // closing the last TX
+    while state.thread_cnt() > 0 {
+        sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+pub fn do_merge(state: State) -> Result<()> {
+    let tx = spawn_merge_workers(state.clone());
     periodic_info(state.clone());
 
-    plan_merge(tx, state)
+    if state.from_plan() {
+        // a reviewed plan may leave packages out, so it isn't a full reify
+        // under the policies and they aren't recorded
+        reify_from_plan(tx, state.clone())?;
+    } else {
+        plan_merge(tx, state.clone())?;
+        if state.is_scoped() {
+            // the packages left out may still be missing files the
+            // policies want, so the next full reify has to look
+            info!("Not recording the file and version policies, the reify was scoped");
+        } else if state.uncommitted_cnt() == 0 {
+            let state_dir = state.artifact_db_state_dir()?;
+            state.file_policy()?.record(&state_dir)?;
+            state.version_policy()?.record(&state_dir)?;
+        } else {
+            info!("Not recording the file and version policies, some packages weren't committed");
+        }
+    }
+    info!(
+        "Done updating artifact DB, {} not in the repo, {} failed downloads, {} packages not committed, {} checksum mismatches, {} artifacts without checksums",
+        state.not_found_cnt().separate_with_commas(),
+        state.failed_download_cnt().separate_with_commas(),
+        state.uncommitted_cnt().separate_with_commas(),
+        state.checksum_mismatch_cnt().separate_with_commas(),
+        state.unverified_cnt().separate_with_commas()
+    );
+    Ok(())
 }
 
 pub fn plan_merge_to_console(state: State) -> Result<()> {
     let (tx, rx) = flume::bounded::<MergeCmd>(100);
-    let _state_clone = state.clone();
-    thread::spawn(move || {
+    let format = state.plan_format();
+    let repo_url = state.repo_url().ok();
+    let mut plan_writer = if state.save_plan() {
+        Some(PlanWriter::create(
+            &state.plan_file()?,
+            &state.crawl_to_merge()?,
+            &state.artifact_db()?,
+            state.file_policy()?,
+            state.snapshots(),
+        )?)
+    } else {
+        None
+    };
+    let printer = thread::spawn(move || -> Result<()> {
+        let mut printer = PlanPrinter::new(format, stdout().lock(), repo_url);
+        printer.start()?;
+        // read until `plan_merge` drops the sender, past the
+        // `End`s meant for merge workers
         for x in rx {
-            match x {
-                MergeCmd::End => {
-                    return;
-                }
-                MergeCmd::Merge(merge_group) => {
-                    for y in merge_group.entries {
-                        println!("{:?}", y);
-                    }
+            if let MergeCmd::Merge(merge_group) = x {
+                printer.print_group(&merge_group)?;
+                if let Some(w) = plan_writer.as_mut() {
+                    w.write_group(&merge_group)?;
                 }
             }
         }
+        if let Some(w) = plan_writer {
+            w.finish()?;
+        }
+        printer.finish()
     });
 
-    plan_merge(tx, state)
+    let planned = plan_merge(tx, state);
+    // wait for everything to be printed before returning
+    match printer.join() {
+        Ok(printed) => planned.and(printed),
+        Err(_) => bail!("The plan printer panicked"),
+    }
 }
 
 pub fn plan_merge(dest: Sender<MergeCmd>, state: State) -> Result<()> {
-    let crawl_db = state.latest_crawl()?;
+    let crawl_db = state.crawl_to_merge()?;
     let start = Instant::now();
     let artifact_db = state.artifact_db()?;
+    let negative_cache = state.negative_cache()?;
+    let discovery = state.discovery();
+    let policy = state.file_policy()?;
+    // if the policy changed, every package may be missing files the
+    // new policy wants, so check each one against the disk
+    let version_policy = state.version_policy()?;
+    let state_dir = state.artifact_db_state_dir()?;
+    let policy_changed = !FilePolicy::recorded(&state_dir)?
+        .unwrap_or_default()
+        .same_files_as(policy)
+        || !VersionPolicy::recorded(&state_dir)?
+            .unwrap_or_default()
+            .same_versions_as(version_policy);
+    if policy_changed {
+        info!("The file or version policy changed since the last reify, looking for missing files");
+    }
+    let backfill = state.backfill() || policy_changed;
+    let updated_cutoff = state.updated_cutoff()?;
+    let only_new_versions = state.only_new_versions();
+    let coordinate_filter = state.coordinate_filter()?;
+    let mut outside_window_cnt = 0usize;
+    let mut filtered_out_cnt = 0usize;
+    let mut known_missing_cnt = 0usize;
+    let mut already_present_cnt = 0usize;
+    let mut backfill_cnt = 0usize;
     let mut meta_data_in_crawl = vec![];
     info!("Planning merge... looking at {:?}", crawl_db);
     for entry in WalkDir::new(&crawl_db).into_iter().filter_map(|e| e.ok()) {
@@ -241,14 +770,95 @@ pub fn plan_merge(dest: Sender<MergeCmd>, state: State) -> Result<()> {
         meta_data_in_crawl.len()
     );
 
+    // one bad file shouldn't stop the plan, so collect them and report at the end
+    let mut plan_errors: Vec<PlanError> = vec![];
+    let too_many_errors = |errors: &Vec<PlanError>| {
+        state
+            .max_errors()
+            .is_some_and(|max_errors| errors.len() > max_errors)
+    };
     for (crawl_id, crawl_md) in meta_data_in_crawl.iter().enumerate() {
-        let mut md_bytes = vec![];
-        // in a block so the file gets closed
+        if too_many_errors(&plan_errors) {
+            break;
+        }
+        let md_bytes = match read(crawl_md) {
+            Ok(v) => v,
+            Err(e) => {
+                plan_errors.push(PlanError::new(crawl_md, e));
+                continue;
+            }
+        };
+        match metadata_kind(&md_bytes) {
+            Ok(MetadataKind::Artifact) => {}
+            // planned along with its artifact
+            Ok(MetadataKind::Version) => continue,
+            Ok(MetadataKind::Group) => {
+                let dest_file = match crawl_md.strip_prefix(&crawl_db) {
+                    Ok(rel) => artifact_db.join(rel),
+                    Err(e) => {
+                        plan_errors.push(PlanError::new(crawl_md, e));
+                        continue;
+                    }
+                };
+                let group_dir = crawl_md
+                    .parent()
+                    .and_then(|p| p.strip_prefix(&crawl_db).ok())
+                    .map(|p| p.to_string_lossy().replace('/', "."))
+                    .unwrap_or_default();
+                if !coordinate_filter.allows_group(&group_dir) {
+                    filtered_out_cnt += 1;
+                    continue;
+                }
+                let existing = read(&dest_file).ok();
+                if existing.as_deref() != Some(&md_bytes[..]) {
+                    let reason = match existing {
+                        Some(_) => PlanReason::Changed,
+                        None => PlanReason::New,
+                    };
+                    let commit = MergeEntry {
+                        source_url: None,
+                        source_file: Some(crawl_md.clone()),
+                        dest_file,
+                        expand: false,
+                    };
+                    dest.send(MergeCmd::Merge(MergeGroup::for_group_metadata(
+                        vec![commit],
+                        &group_dir,
+                        reason,
+                    )))?;
+                }
+                continue;
+            }
+            Err(e) => {
+                plan_errors.push(PlanError::new(crawl_md, e));
+                continue;
+            }
+        }
+        let crawl_meta = match version_from_metadata(&md_bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                plan_errors.push(PlanError::new(crawl_md, e));
+                continue;
+            }
+        };
+        // not updated in the window, so leave it for a later reify. Without
+        // a `lastUpdated` there's no telling, so it's planned
+        if let (Some(cutoff), Some(updated)) = (updated_cutoff, crawl_meta.last_updated)
+            && updated < cutoff
         {
-            let mut f = File::open(&crawl_md)?;
-            f.read_to_end(&mut md_bytes)?;
+            outside_window_cnt += 1;
+            continue;
         }
-        let (group_id, artifact_id, add_files) = version_from_metadata(&md_bytes)?;
+        if !coordinate_filter.allows(&crawl_meta.group_id, &crawl_meta.artifact_id) {
+            filtered_out_cnt += 1;
+            continue;
+        }
+        let ArtifactMetadata {
+            group_id,
+            artifact_id,
+            versions,
+            ..
+        } = crawl_meta;
         if crawl_id > 0 && crawl_id % 1000 == 0 {
             let run_time = Instant::now().duration_since(start).as_secs() as f64;
             let total = meta_data_in_crawl.len();
@@ -278,64 +888,197 @@ pub fn plan_merge(dest: Sender<MergeCmd>, state: State) -> Result<()> {
             }
         }
 
-        // if the two files are the same, process the next one
-        if art_bytes == md_bytes {
-            continue;
+        // the same versions as last time, so only rebuilt snapshots can have
+        // anything new
+        let unchanged = art_bytes == md_bytes && !backfill;
+
+        let art_versions = version_from_metadata(&art_bytes)
+            .map(|m| m.versions)
+            .unwrap_or_default();
+        let mut selected = version_policy.select(versions);
+        if only_new_versions {
+            selected.retain(|v| !art_versions.contains(v));
         }
+        let (snapshots, releases): (Vec<String>, Vec<String>) =
+            selected.into_iter().partition(|v| is_snapshot(v));
+        let mut diff_files = HashSet::new();
+        if !unchanged {
+            let add_files = planned_paths(&group_id, &artifact_id, &releases, discovery, policy);
 
-        let (_art_group_id, _art_artifact_id, art_add_files) =
-            match version_from_metadata(&art_bytes) {
-                Ok(v) => v,
-                Err(_) => (group_id.clone(), artifact_id.clone(), vec![]),
+            let art_add_files = if backfill {
+                vec![]
+            } else {
+                let art_releases: Vec<String> = version_policy
+                    .select(art_versions)
+                    .into_iter()
+                    .filter(|v| !is_snapshot(v))
+                    .collect();
+                planned_paths(&group_id, &artifact_id, &art_releases, discovery, policy)
             };
 
-        let mut diff_files = HashSet::new();
-        // all the potential files from the current crawl's maven metadata
-        for v in &add_files {
-            diff_files.insert(v.clone());
+            // all the potential files from the current crawl's maven metadata
+            for v in &add_files {
+                diff_files.insert(v.clone());
+            }
+
+            // subtract the files from the artifact DB's maven metadata
+            for v in &art_add_files {
+                diff_files.remove(v);
+            }
         }
 
-        // subtract the files from the artifact DB's maven metadata
-        for v in &art_add_files {
-            diff_files.remove(v);
+        // snapshots are rebuilt under the same version, so compare the
+        // version level metadata, which changes with each build
+        let mut version_commits = vec![];
+        let mut snapshot_changed = false;
+        for v in &snapshots {
+            let crawl_vmd_file = crawl_md.with_file_name(v).join(GOLD_FILE);
+            let art_vmd_file = artifact_gold_file.with_file_name(v).join(GOLD_FILE);
+            let crawl_vmd = read(&crawl_vmd_file).ok();
+            let art_vmd = read(&art_vmd_file).ok();
+            if crawl_vmd.is_some() && crawl_vmd == art_vmd && !backfill {
+                continue;
+            }
+            // without version metadata there's no sign of a new build
+            if crawl_vmd.is_none() && unchanged {
+                continue;
+            }
+            snapshot_changed |= crawl_vmd.is_some() && crawl_vmd != art_vmd;
+            let builds = state.snapshots();
+            let mut new_files: HashSet<String> = snapshot_paths(
+                &group_id,
+                &artifact_id,
+                v,
+                crawl_vmd.as_deref(),
+                builds,
+                discovery,
+                policy,
+            )
+            .into_iter()
+            .collect();
+            // the files of builds already in the artifact db. With every build,
+            // the directory is expanded again and only new files are fetched
+            if art_vmd.is_some() && !backfill && builds == SnapshotBuilds::Latest {
+                for p in snapshot_paths(
+                    &group_id,
+                    &artifact_id,
+                    v,
+                    art_vmd.as_deref(),
+                    builds,
+                    discovery,
+                    policy,
+                ) {
+                    new_files.remove(&p);
+                }
+            }
+            diff_files.extend(new_files);
+            if crawl_vmd.is_some() {
+                version_commits.push(MergeEntry {
+                    source_url: None,
+                    source_file: Some(crawl_vmd_file),
+                    dest_file: art_vmd_file,
+                    expand: false,
+                });
+            }
         }
 
         let mut to_send = vec![];
 
         for url in diff_files {
+            // it 404'd last time we asked
+            if negative_cache.is_known_missing(&url) {
+                known_missing_cnt += 1;
+                continue;
+            }
             let dest_file = artifact_db.join(&url);
+            if backfill && dest_file.is_file() {
+                already_present_cnt += 1;
+                continue;
+            }
             to_send.push(MergeEntry {
+                expand: url.ends_with("/"),
                 source_url: Some(url),
                 source_file: None,
                 dest_file,
-                state: state.clone(),
             });
         }
 
+        let reason = if art_bytes.is_empty() {
+            PlanReason::New
+        } else if art_bytes == md_bytes && !snapshot_changed {
+            // backfilling found nothing missing in an up to date package
+            if to_send.is_empty() {
+                continue;
+            }
+            backfill_cnt += to_send.len();
+            PlanReason::Backfill
+        } else {
+            PlanReason::Changed
+        };
+
+        // the version level metadata is committed before the artifact's
+        to_send.extend(version_commits);
+
         to_send.push(MergeEntry {
             source_url: None,
             source_file: Some(crawl_md.clone()),
             dest_file: artifact_gold_file,
-            state: state.clone(),
+            expand: false,
         });
 
         dest.send(MergeCmd::Merge(MergeGroup {
             entries: to_send,
             group_id,
-            artifact_id,
+            artifact_id: Some(artifact_id),
+            reason,
         }))?;
     }
 
-    // tell all the threads to end
-    for _ in 0..state.thread_cnt() + 5 {
-        dest.send(MergeCmd::End)?;
+    info!(
+        "Skipped {} artifacts that weren't in the repo last time",
+        known_missing_cnt.separate_with_commas()
+    );
+    if let Some(cutoff) = updated_cutoff {
+        info!(
+            "Skipped {} packages not updated since {}",
+            outside_window_cnt.separate_with_commas(),
+            cutoff
+        );
     }
-
-    // wait for the threads to end before returning and
-    // closing the last TX
-    while state.thread_cnt() > 0 {
-        sleep(Duration::from_millis(100));
+    if !coordinate_filter.is_empty() {
+        info!(
+            "Skipped {} metadata files outside the group and artifact globs",
+            filtered_out_cnt.separate_with_commas()
+        );
+    }
+    let errors_file = crawl_db.join(PLAN_ERRORS_FILE);
+    write_json(&errors_file, &plan_errors)?;
+    if !plan_errors.is_empty() {
+        warn!(
+            "Skipped {} metadata files that couldn't be planned, listed in {:?}",
+            plan_errors.len().separate_with_commas(),
+            errors_file
+        );
+        for pe in plan_errors.iter().take(20) {
+            warn!("Couldn't plan {:?} {}", pe.path, pe.error);
+        }
+    }
+    if backfill {
+        info!(
+            "Backfilling {} files in unchanged packages, {} expected files already in the artifact DB",
+            backfill_cnt.separate_with_commas(),
+            already_present_cnt.separate_with_commas()
+        );
     }
 
+    // tell all the threads to end
+    end_merge_workers(&dest, &state)?;
+
+    if too_many_errors(&plan_errors) {
+        bail!(
+            "Stopped planning after {} errors, more than --max-errors",
+            plan_errors.len().separate_with_commas()
+        );
+    }
     Ok(())
 }
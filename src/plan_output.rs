@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use thousands::Separable;
+
+use crate::{
+    plan_merge::{MergeGroup, PlanReason, base_path_from_group_and_artifact},
+    snapshot::SNAPSHOT_SUFFIX,
+};
+
+/// How `plan` prints what reify would do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PlanFormat {
+    /// a line per package, then the totals
+    #[default]
+    Summary,
+    /// a JSON object per file to download
+    Jsonl,
+    /// a CSV row per file to download, after a header
+    Csv,
+}
+
+/// A file reify would download
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedDownload {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: Option<String>,
+    /// what follows `{artifact}-{version}` in the file name, e.g., `-sources.jar`.
+    /// `None` for a version directory whose files are found while reifying
+    pub suffix: Option<String>,
+    /// the path in the repo
+    pub path: String,
+    /// the URL to fetch, when the repo is known
+    pub source_url: Option<String>,
+    pub dest_file: PathBuf,
+    pub reason: PlanReason,
+}
+
+const CSV_HEADER: &str = "group_id,artifact_id,version,suffix,path,source_url,dest_file,reason";
+
+/// skip a snapshot file's build, `SNAPSHOT` or a timestamp and build number like `20250101.120000-3`
+fn skip_snapshot_build(rest: &str) -> &str {
+    if let Some(after) = rest.strip_prefix("SNAPSHOT") {
+        return after;
+    }
+    let after_timestamp = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match after_timestamp.strip_prefix('-') {
+        Some(build) => build.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => rest,
+    }
+}
+
+/// the version directory the path is in, and the file name's suffix
+fn version_and_suffix(group: &MergeGroup, path: &str) -> (Option<String>, Option<String>) {
+    let artifact_id = match group.artifact_id() {
+        Some(a) => a,
+        None => return (None, None),
+    };
+    let base = base_path_from_group_and_artifact(group.group_id(), artifact_id);
+    let (version, file) = match path.strip_prefix(&base).and_then(|r| r.split_once('/')) {
+        Some(v) => v,
+        None => return (None, None),
+    };
+    if file.is_empty() {
+        return (Some(version.to_string()), None);
+    }
+    let suffix = match version.strip_suffix(SNAPSHOT_SUFFIX) {
+        Some(release) => file
+            .strip_prefix(&format!("{}-{}-", artifact_id, release))
+            .map(skip_snapshot_build),
+        None => file.strip_prefix(&format!("{}-{}", artifact_id, version)),
+    };
+    (Some(version.to_string()), suffix.map(|s| s.to_string()))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl PlannedDownload {
+    /// the downloads in the group. Metadata copied from the crawl isn't downloaded
+    pub fn from_group(group: &MergeGroup, repo_url: &Option<String>) -> Vec<PlannedDownload> {
+        group
+            .entries()
+            .iter()
+            .flat_map(|entry| {
+                let path = entry.source_url.clone()?;
+                let (version, suffix) = version_and_suffix(group, &path);
+                Some(PlannedDownload {
+                    group_id: group.group_id().to_string(),
+                    artifact_id: group.artifact_id().unwrap_or_default().to_string(),
+                    version,
+                    suffix,
+                    source_url: repo_url
+                        .as_ref()
+                        .map(|repo| format!("{}/{}", repo.trim_end_matches('/'), path)),
+                    path,
+                    dest_file: entry.dest_file.clone(),
+                    reason: group.reason(),
+                })
+            })
+            .collect()
+    }
+
+    fn csv_row(&self) -> String {
+        [
+            self.group_id.as_str(),
+            &self.artifact_id,
+            self.version.as_deref().unwrap_or_default(),
+            self.suffix.as_deref().unwrap_or_default(),
+            &self.path,
+            self.source_url.as_deref().unwrap_or_default(),
+            &self.dest_file.to_string_lossy(),
+            self.reason.as_str(),
+        ]
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// Writes the plan as it's made
+pub struct PlanPrinter<W: Write> {
+    format: PlanFormat,
+    out: W,
+    repo_url: Option<String>,
+    /// packages and downloads for each reason
+    totals: BTreeMap<PlanReason, (usize, usize)>,
+}
+
+impl<W: Write> PlanPrinter<W> {
+    pub fn new(format: PlanFormat, out: W, repo_url: Option<String>) -> PlanPrinter<W> {
+        PlanPrinter {
+            format,
+            out,
+            repo_url,
+            totals: BTreeMap::new(),
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        if self.format == PlanFormat::Csv {
+            writeln!(self.out, "{}", CSV_HEADER)?;
+        }
+        Ok(())
+    }
+
+    pub fn print_group(&mut self, group: &MergeGroup) -> Result<()> {
+        let downloads = PlannedDownload::from_group(group, &self.repo_url);
+        let totals = self.totals.entry(group.reason()).or_default();
+        totals.0 += 1;
+        totals.1 += downloads.len();
+        match self.format {
+            PlanFormat::Jsonl => {
+                for d in &downloads {
+                    serde_json::to_writer(&mut self.out, d)?;
+                    writeln!(self.out)?;
+                }
+            }
+            PlanFormat::Csv => {
+                for d in &downloads {
+                    writeln!(self.out, "{}", d.csv_row())?;
+                }
+            }
+            PlanFormat::Summary => {
+                let metadata = group.entries().len() - downloads.len();
+                writeln!(
+                    self.out,
+                    "{:<8} {} {} downloads, {} metadata files",
+                    group.reason().as_str(),
+                    group.package(),
+                    downloads.len().separate_with_commas(),
+                    metadata
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        if self.format == PlanFormat::Summary {
+            for (reason, (packages, downloads)) in &self.totals {
+                writeln!(
+                    self.out,
+                    "Total {}: {} packages, {} downloads",
+                    reason.as_str(),
+                    packages.separate_with_commas(),
+                    downloads.separate_with_commas()
+                )?;
+            }
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(path: &str) -> (Option<String>, Option<String>) {
+        let group = MergeGroup::new(vec![], "org.example", "snap", PlanReason::New);
+        version_and_suffix(&group, path)
+    }
+
+    fn some(version: &str, suffix: &str) -> (Option<String>, Option<String>) {
+        (Some(version.to_string()), Some(suffix.to_string()))
+    }
+
+    #[test]
+    fn release_files() {
+        assert_eq!(
+            split("org/example/snap/1.0/snap-1.0.jar"),
+            some("1.0", ".jar")
+        );
+        assert_eq!(
+            split("org/example/snap/1.0/snap-1.0-sources.jar"),
+            some("1.0", "-sources.jar")
+        );
+        assert_eq!(
+            split("org/example/snap/1.0/"),
+            (Some("1.0".to_string()), None)
+        );
+        assert_eq!(split("org/example/other/1.0/other-1.0.jar"), (None, None));
+    }
+
+    #[test]
+    fn snapshot_builds() {
+        let dir = "org/example/snap/1.1-SNAPSHOT/";
+        assert_eq!(
+            split(&format!("{}snap-1.1-20250101.120000-3.jar", dir)),
+            some("1.1-SNAPSHOT", ".jar")
+        );
+        assert_eq!(
+            split(&format!("{}snap-1.1-20250101.120000-3-sources.jar", dir)),
+            some("1.1-SNAPSHOT", "-sources.jar")
+        );
+        // deployed without timestamps
+        assert_eq!(
+            split(&format!("{}snap-1.1-SNAPSHOT.pom", dir)),
+            some("1.1-SNAPSHOT", ".pom")
+        );
+    }
+
+    #[test]
+    fn group_metadata_has_no_version() {
+        let group = MergeGroup::for_group_metadata(vec![], "org.example", PlanReason::New);
+        assert_eq!(
+            version_and_suffix(&group, "org/example/maven-metadata.xml"),
+            (None, None)
+        );
+    }
+}
@@ -1,20 +1,126 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
 use std::{
-    fs::{File, create_dir_all, remove_file},
+    fs::{File, copy, create_dir_all, hard_link, remove_file, rename},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use log::error;
 
-use crate::run_state::State;
+use crate::{
+    json_file::{read_json, write_json},
+    run_state::State,
+};
+
+/// The validators the server sent with a response. They're
+/// saved next to the file so the next crawl can make a
+/// conditional request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheHeaders {
+    pub last_modified: Option<String>,
+    pub etag: Option<String>,
+}
+
+impl CacheHeaders {
+    /// where the headers for `file` are stored
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".headers.json");
+        PathBuf::from(name)
+    }
+
+    /// the headers stored for `file`, if there are any
+    pub fn load(file: &Path) -> Option<CacheHeaders> {
+        let path = CacheHeaders::path_for(file);
+        if !path.is_file() {
+            return None;
+        }
+        read_json(&path).ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_modified.is_none() && self.etag.is_none()
+    }
+}
+
+/// make the directory for `dest` and remove anything already there. The
+/// old file is removed rather than overwritten because it may be hard linked
+fn prepare_dest(dest: &Path) -> Result<()> {
+    let dir = match dest.parent() {
+        Some(d) => d,
+        None => bail!("Couldn't get parent directory for {:?}", dest),
+    };
+    create_dir_all(dir)?;
+    if dest.exists() {
+        remove_file(dest)?;
+    }
+    Ok(())
+}
+
+/// hard link `src` to `dest`, copying if the link fails (e.g., across
+/// file systems). Anything already at `dest` is replaced
+pub fn link_or_copy(src: &Path, dest: &Path) -> Result<()> {
+    prepare_dest(dest)?;
+    if hard_link(src, dest).is_err() {
+        copy(src, dest)?;
+    }
+    Ok(())
+}
+
+/// a temp file name next to `dest` that no other writer of `dest` will
+/// pick, so two threads writing the same file can't rename each other's
+/// half written file into place. The directory is created if needed
+pub fn temp_path_for(dest: &Path) -> Result<PathBuf> {
+    let (dir, name) = match (dest.parent(), dest.file_name()) {
+        (Some(d), Some(n)) => (d, n.to_string_lossy()),
+        _ => bail!("Couldn't get parent directory for {:?}", dest),
+    };
+    create_dir_all(dir)?;
+    Ok(dir.join(format!(
+        ".{}.{:08x}{}",
+        name,
+        rand::random::<u32>(),
+        TEMP_SUFFIX
+    )))
+}
+
+/// write the bytes to a temp file next to `dest`, fsync it, and rename it
+/// into place so a kill mid-write never leaves a truncated file at `dest`
+pub fn write_atomically(dest: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = temp_path_for(dest)?;
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    if let Err(e) = rename(&tmp_path, dest) {
+        let _ = remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    // make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = dest.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// copy `src` to `dest`, replacing anything already at `dest`. Use this
+/// rather than `link_or_copy` when `src` may be rewritten later
+pub fn replace_with_copy(src: &Path, dest: &Path) -> Result<()> {
+    prepare_dest(dest)?;
+    copy(src, dest)?;
+    Ok(())
+}
 
 pub struct ResponseData {
     url: String,
     file_path: String,
     data: Vec<u8>,
     mime_type: String,
+    cache_headers: CacheHeaders,
     state: State,
 }
 
@@ -24,6 +130,7 @@ impl ResponseData {
         server_prefix: &str,
         data: Vec<u8>,
         mime_type: String,
+        cache_headers: CacheHeaders,
         state: State,
     ) -> Result<ResponseData> {
         state.repo_url()?;
@@ -33,6 +140,7 @@ impl ResponseData {
             url,
             data,
             mime_type,
+            cache_headers,
             state,
         })
     }
@@ -44,6 +152,10 @@ impl ResponseData {
         self.mime_type.clone()
     }
 
+    pub fn cache_headers(&self) -> &CacheHeaders {
+        &self.cache_headers
+    }
+
     pub fn base_url(&self) -> String {
         self.state.repo_url().expect("This has been pre-vetted")
     }
@@ -92,41 +204,59 @@ impl ResponseData {
     }
 
     pub fn file_path(&self) -> PathBuf {
-        let path = self.state.crawl_db_dest_dir().join(&self.file_path);
-        path
+        self.state.crawl_db_dest_dir().join(&self.file_path)
     }
+    /// save the data and the cache headers. The data is written to a
+    /// temp file and renamed into place because the existing file may
+    /// be hard linked into a previous crawl
     pub fn save(&self) -> Result<()> {
         let path = self.file_path();
-        let dir = path.parent().expect("Get parent");
-        create_dir_all(dir)?;
-        let mut file = File::create(path)?;
-        file.write_all(&self.data)?;
+        write_atomically(&path, &self.data)?;
+
+        let headers_path = CacheHeaders::path_for(&path);
+        if !self.cache_headers.is_empty() {
+            write_json(&headers_path, &self.cache_headers)?;
+        } else if headers_path.exists() {
+            remove_file(&headers_path)?;
+        }
         Ok(())
     }
     /// Take an HTML page and find all the down-links on the page
     pub fn html_to_links(&self) -> Vec<String> {
+        self.links_matching(|href| href.ends_with("/") || href.ends_with(GOLD_FILE))
+    }
+
+    /// Take an HTML page and find all the links to files (not
+    /// directories) on the page
+    pub fn html_to_file_links(&self) -> Vec<String> {
+        self.links_matching(|href| {
+            !href.ends_with("/") && !href.starts_with("?") && !href.starts_with("#")
+        })
+    }
+
+    /// find the links on the page that stay in the repo and that `accept` likes
+    fn links_matching(&self, accept: impl Fn(&str) -> bool) -> Vec<String> {
         let mut ret = vec![];
         if let Ok(string) = String::from_utf8(self.data.clone()) {
             let document = Html::parse_document(&string);
             let gimme_a = Selector::parse("a").expect("Should be able to parse 'a'");
             for a in document.select(&gimme_a) {
-                if let Some(href) = a.attr("href") {
-                    if href.len() > 1
-                        && !href.starts_with(".")
-                        && (!href.starts_with("http") || href.starts_with(&self.base_url()))
-                        && (href.ends_with("/") || href.ends_with(GOLD_FILE))
-                    {
-                        let target = if href.starts_with(&self.base_url()) {
-                            href.to_string()
-                        } else {
-                            format!("{}{}", self.url, href)
-                        };
-                        if target == self.url {
-                            println!("url {} page {}", self.url, string);
-                            panic!("Yak");
-                        }
-                        ret.push(target);
+                if let Some(href) = a.attr("href")
+                    && href.len() > 1
+                    && !href.starts_with(".")
+                    && (!href.starts_with("http") || href.starts_with(&self.base_url()))
+                    && accept(href)
+                {
+                    let target = if href.starts_with(&self.base_url()) {
+                        href.to_string()
+                    } else {
+                        format!("{}{}", self.url, href)
+                    };
+                    if target == self.url {
+                        println!("url {} page {}", self.url, string);
+                        panic!("Yak");
                     }
+                    ret.push(target);
                 }
             }
         }
@@ -135,3 +265,7 @@ impl ResponseData {
     }
 }
 pub const GOLD_FILE: &str = "maven-metadata.xml";
+
+/// the suffix of files that are being written. Any left
+/// behind are from a process that was killed
+pub const TEMP_SUFFIX: &str = ".tmp";
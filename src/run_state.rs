@@ -1,9 +1,9 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     fs::create_dir_all,
     path::PathBuf,
     sync::{
-        Arc, Mutex,
+        Arc, Mutex, OnceLock,
         atomic::{AtomicU64, AtomicUsize, Ordering},
     },
     time::{Duration, Instant, SystemTime},
@@ -11,8 +11,44 @@ use std::{
 
 use anyhow::{Result, bail};
 use chrono::prelude::*;
+use log::{info, warn};
 
-use crate::args::Args;
+use crate::{
+    args::{Args, Mode},
+    checkpoint::CrawlCheckpoint,
+    coordinate_filter::CoordinateFilter,
+    crawl_manifest::CrawlManifest,
+    discovery::ArtifactDiscovery,
+    file_policy::FilePolicy,
+    negative_cache::NegativeCache,
+    plan_file::{PLAN_FILE, PlanJournal},
+    plan_output::PlanFormat,
+    snapshot::SnapshotBuilds,
+    version_policy::VersionPolicy,
+};
+
+/// The pages of a crawl: those waiting to be fetched, those
+/// a thread has picked up but not finished, and those that are done
+#[derive(Default)]
+struct CrawlQueue {
+    pending: VecDeque<String>,
+    in_progress: HashSet<String>,
+    visited: HashSet<String>,
+    /// pages visited since the last checkpoint took them
+    newly_visited: Vec<String>,
+}
+
+// the visited set can be millions of entries, so just show the sizes
+impl std::fmt::Debug for CrawlQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrawlQueue")
+            .field("pending", &self.pending.len())
+            .field("in_progress", &self.in_progress.len())
+            .field("visited", &self.visited.len())
+            .field("newly_visited", &self.newly_visited.len())
+            .finish()
+    }
+}
 
 /// The state of the running job
 /// An `Arc` of this gets passed everywhere
@@ -23,12 +59,32 @@ pub struct RunState {
     fetch_cnt: AtomicUsize,
     asset_fetch_cnt: AtomicUsize,
     threads_in_429: AtomicU64,
-    queue: Mutex<VecDeque<String>>,
+    queue: Mutex<CrawlQueue>,
+    /// the visited pages as of the last checkpoint. Kept apart from the
+    /// queue so a checkpoint doesn't copy the whole set with the queue locked
+    checkpointed_visited: Mutex<Vec<String>>,
     running_threads: AtomicUsize,
     total_added_pages: AtomicUsize,
     total_bytes: AtomicUsize,
+    prior_run_millis: AtomicU64,
+    metadata_cnt: AtomicUsize,
+    not_modified_cnt: AtomicUsize,
+    checksum_skip_cnt: AtomicUsize,
+    checksum_mismatch_cnt: AtomicUsize,
+    unverified_cnt: AtomicUsize,
+    not_found_cnt: AtomicUsize,
+    failed_download_cnt: AtomicUsize,
+    uncommitted_cnt: AtomicUsize,
+    error_cnt: AtomicUsize,
+    previous_crawl: OnceLock<Option<PathBuf>>,
+    negative_cache: OnceLock<NegativeCache>,
+    file_policy: OnceLock<FilePolicy>,
+    version_policy: OnceLock<VersionPolicy>,
+    coordinate_filter: OnceLock<CoordinateFilter>,
+    plan_journal: OnceLock<PlanJournal>,
     start: Instant,
     start_time: SystemTime,
+    crawl_started_at: Mutex<DateTime<Utc>>,
 }
 
 impl RunState {
@@ -65,18 +121,119 @@ impl RunState {
     pub fn inc_asset_fetch_cnt(&self) -> usize {
         self.asset_fetch_cnt.fetch_add(1, Ordering::Relaxed) + 1
     }
+    /// a `maven-metadata.xml` file was saved to the crawl db
+    pub fn inc_metadata_cnt(&self) -> usize {
+        self.metadata_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn metadata_cnt(&self) -> usize {
+        self.metadata_cnt.load(Ordering::Relaxed)
+    }
+
+    /// a metadata file was reused from the previous crawl
+    pub fn inc_not_modified_cnt(&self) -> usize {
+        self.not_modified_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn not_modified_cnt(&self) -> usize {
+        self.not_modified_cnt.load(Ordering::Relaxed)
+    }
+
+    /// a metadata file wasn't downloaded because its `.sha1`
+    /// matched an existing copy
+    pub fn inc_checksum_skip_cnt(&self) -> usize {
+        self.checksum_skip_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn checksum_skip_cnt(&self) -> usize {
+        self.checksum_skip_cnt.load(Ordering::Relaxed)
+    }
+
+    /// an artifact didn't match its checksum files
+    pub fn inc_checksum_mismatch_cnt(&self) -> usize {
+        self.checksum_mismatch_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn checksum_mismatch_cnt(&self) -> usize {
+        self.checksum_mismatch_cnt.load(Ordering::Relaxed)
+    }
+
+    /// an artifact had no checksum files to verify it against
+    pub fn inc_unverified_cnt(&self) -> usize {
+        self.unverified_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn unverified_cnt(&self) -> usize {
+        self.unverified_cnt.load(Ordering::Relaxed)
+    }
+
+    /// a planned artifact isn't in the repo
+    pub fn inc_not_found_cnt(&self) -> usize {
+        self.not_found_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn not_found_cnt(&self) -> usize {
+        self.not_found_cnt.load(Ordering::Relaxed)
+    }
+
+    /// a planned artifact couldn't be fetched or written
+    pub fn inc_failed_download_cnt(&self) -> usize {
+        self.failed_download_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn failed_download_cnt(&self) -> usize {
+        self.failed_download_cnt.load(Ordering::Relaxed)
+    }
+
+    /// a package's metadata wasn't copied to the artifact db
+    /// because some of its artifacts failed
+    pub fn inc_uncommitted_cnt(&self) -> usize {
+        self.uncommitted_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn uncommitted_cnt(&self) -> usize {
+        self.uncommitted_cnt.load(Ordering::Relaxed)
+    }
+
+    pub fn inc_error_cnt(&self) -> usize {
+        self.error_cnt.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn error_cnt(&self) -> usize {
+        self.error_cnt.load(Ordering::Relaxed)
+    }
+
     pub fn new(args: Args) -> Arc<RunState> {
+        let start_time = SystemTime::now();
         Arc::new(RunState {
             args,
             total_bytes: AtomicUsize::new(0),
             fetch_cnt: AtomicUsize::new(0),
             asset_fetch_cnt: AtomicUsize::new(0),
             threads_in_429: AtomicU64::new(0),
-            queue: Mutex::new(VecDeque::new()),
+            queue: Mutex::new(CrawlQueue::default()),
+            checkpointed_visited: Mutex::new(vec![]),
             running_threads: AtomicUsize::new(0),
             total_added_pages: AtomicUsize::new(0),
+            prior_run_millis: AtomicU64::new(0),
+            metadata_cnt: AtomicUsize::new(0),
+            not_modified_cnt: AtomicUsize::new(0),
+            checksum_skip_cnt: AtomicUsize::new(0),
+            checksum_mismatch_cnt: AtomicUsize::new(0),
+            unverified_cnt: AtomicUsize::new(0),
+            not_found_cnt: AtomicUsize::new(0),
+            failed_download_cnt: AtomicUsize::new(0),
+            uncommitted_cnt: AtomicUsize::new(0),
+            error_cnt: AtomicUsize::new(0),
+            previous_crawl: OnceLock::new(),
+            negative_cache: OnceLock::new(),
+            file_policy: OnceLock::new(),
+            version_policy: OnceLock::new(),
+            coordinate_filter: OnceLock::new(),
+            plan_journal: OnceLock::new(),
             start: Instant::now(),
-            start_time: SystemTime::now(),
+            start_time,
+            crawl_started_at: Mutex::new(start_time.into()),
         })
     }
 
@@ -90,6 +247,12 @@ impl RunState {
         utc.format("%Y_%m_%d_%H_%M_%S").to_string()
     }
 
+    /// when the crawl started. For a resumed crawl, this is
+    /// when the original run started
+    pub fn crawl_started_at(&self) -> DateTime<Utc> {
+        *self.crawl_started_at.lock().expect("Lock start time")
+    }
+
     pub fn mirror_url(&self) -> &Option<String> {
         self.args.mirror_url()
     }
@@ -106,14 +269,46 @@ impl RunState {
         self.args.max_threads()
     }
 
+    /// how long the run has taken, including the time spent
+    /// before a resumed crawl was interrupted
     pub fn run_duration(&self) -> Duration {
         Instant::now().duration_since(self.start)
+            + Duration::from_millis(self.prior_run_millis.load(Ordering::Relaxed))
+    }
+
+    /// how often to checkpoint the crawl
+    pub fn checkpoint_secs(&self) -> u64 {
+        self.args.checkpoint_secs()
+    }
+
+    /// the crawl db while crawling, where `--crawl-db` is required
+    fn crawl_db_for_crawl(&self) -> PathBuf {
+        self.args
+            .crawl_db()
+            .expect("The crawl command requires --crawl-db")
+    }
+
+    /// the crawl directory being resumed, if any. A bare
+    /// directory name is looked up in the crawl db
+    pub fn resume_dir(&self) -> Option<PathBuf> {
+        self.args.resume().map(|dir| {
+            if dir.is_dir() {
+                dir
+            } else {
+                self.crawl_db_for_crawl().join(dir)
+            }
+        })
     }
 
     /// the directory to put info in
     pub fn crawl_db_dest_dir(&self) -> PathBuf {
-        let sub_dir = format!("{}_crawl_db", self.start_date_string());
-        let ret = self.args.crawl_db().join(sub_dir);
+        let ret = match self.resume_dir() {
+            Some(dir) => dir,
+            None => {
+                let sub_dir = format!("{}_crawl_db", self.start_date_string());
+                self.crawl_db_for_crawl().join(sub_dir)
+            }
+        };
         if !ret.exists() {
             create_dir_all(&ret).expect("Should be able to create directory");
         }
@@ -121,13 +316,65 @@ impl RunState {
         ret
     }
 
-    /// should we "plan" what happens?
-    pub fn plan(&self) -> bool {
-        self.args.plan()
+    /// crawl, plan, reify, audit, or list
+    pub fn mode(&self) -> Mode {
+        self.args.mode()
+    }
+
+    /// how to print the plan
+    pub fn plan_format(&self) -> PlanFormat {
+        self.args.plan_format()
+    }
+
+    /// `plan --save-plan`
+    pub fn save_plan(&self) -> bool {
+        self.args.save_plan()
+    }
+
+    /// `reify --from-plan`
+    pub fn from_plan(&self) -> bool {
+        self.args.from_plan()
+    }
+
+    /// the plan file, `--plan-file` or the one in the crawl being planned from
+    pub fn plan_file(&self) -> Result<PathBuf> {
+        match self.args.plan_file() {
+            Some(path) => Ok(path.clone()),
+            None => Ok(self.crawl_to_merge()?.join(PLAN_FILE)),
+        }
+    }
+
+    /// the crawl directory a saved plan was made from. If `--crawl`
+    /// is given, it has to name the same crawl
+    pub fn plan_crawl(&self, name: &str) -> Result<PathBuf> {
+        let dir = match self.args.crawl() {
+            Some(_) => self.crawl_to_merge()?,
+            None => self.args.crawl_db()?.join(name),
+        };
+        if dir.file_name().is_none_or(|n| n.to_string_lossy() != name) {
+            bail!("The plan was made from crawl {}, not {:?}", name, dir);
+        }
+        if !dir.is_dir() {
+            bail!("Could not find the crawl {} the plan was made from", name);
+        }
+        Ok(dir)
+    }
+
+    /// record finished entries in the journal while reifying from a plan
+    pub fn start_plan_journal(&self, journal: PlanJournal) -> Result<()> {
+        if self.plan_journal.set(journal).is_err() {
+            bail!("The plan journal was already started");
+        }
+        Ok(())
+    }
+
+    /// the journal of the plan being reified, if reifying from a plan
+    pub fn plan_journal(&self) -> Option<&PlanJournal> {
+        self.plan_journal.get()
     }
 
     pub fn queue_len(&self) -> usize {
-        self.queue.lock().expect("Lock queue").len()
+        self.queue.lock().expect("Lock queue").pending.len()
     }
     pub fn thread_cnt(&self) -> usize {
         self.running_threads.load(Ordering::Relaxed)
@@ -141,14 +388,128 @@ impl RunState {
         self.args.artifact_db()
     }
 
-    /// update the artifact_db from the latest crawl
-    pub fn reify_artifact_db(&self) -> bool {
-        self.args.reify_artifact_db()
+    /// the directory in the artifact db where the crawler
+    /// keeps its own bookkeeping
+    pub fn artifact_db_state_dir(&self) -> Result<PathBuf> {
+        let ret = self.artifact_db()?.join(ARTIFACT_DB_STATE_DIR);
+        create_dir_all(&ret)?;
+        Ok(ret)
     }
 
-    /// get the directory that contains the latest crawl
-    pub fn latest_crawl(&self) -> Result<PathBuf> {
-        let dir = self.args.crawl_db();
+    /// the artifact paths that 404'd in earlier reifies, loaded on first use
+    pub fn negative_cache(&self) -> Result<&NegativeCache> {
+        if let Some(v) = self.negative_cache.get() {
+            return Ok(v);
+        }
+        let cache = NegativeCache::load(
+            &self.artifact_db_state_dir()?,
+            self.args.not_found_ttl_days().map(chrono::Duration::days),
+            self.args.recheck_not_found(),
+        )?;
+        Ok(self.negative_cache.get_or_init(|| cache))
+    }
+
+    /// which files to fetch for each version, loaded on first use
+    pub fn file_policy(&self) -> Result<&FilePolicy> {
+        if let Some(v) = self.file_policy.get() {
+            return Ok(v);
+        }
+        let policy = FilePolicy::from_args(&self.args)?;
+        Ok(self.file_policy.get_or_init(|| policy))
+    }
+
+    /// look for missing files in unchanged packages
+    pub fn backfill(&self) -> bool {
+        self.args.backfill()
+    }
+
+    /// repair what the audit finds
+    pub fn repair(&self) -> bool {
+        self.args.repair()
+    }
+
+    /// which builds of snapshot versions to fetch
+    pub fn snapshots(&self) -> SnapshotBuilds {
+        self.args.snapshots()
+    }
+
+    /// how many planned downloads to estimate from, all if `None`
+    pub fn sample(&self) -> Option<usize> {
+        self.args.sample()
+    }
+
+    /// the download speed to assume when estimating, in megabits per second
+    pub fn bandwidth_mbps(&self) -> f64 {
+        self.args.bandwidth_mbps()
+    }
+
+    /// how many bad crawl entries before the plan stops
+    pub fn max_errors(&self) -> Option<usize> {
+        self.args.max_errors()
+    }
+
+    /// which versions of each artifact to fetch, loaded on first use
+    pub fn version_policy(&self) -> Result<&VersionPolicy> {
+        if let Some(v) = self.version_policy.get() {
+            return Ok(v);
+        }
+        let policy = VersionPolicy::from_args(&self.args)?;
+        Ok(self.version_policy.get_or_init(|| policy))
+    }
+
+    /// the groups and artifacts to crawl and reify, from the
+    /// `--include-*` and `--exclude-*` globs
+    pub fn coordinate_filter(&self) -> Result<&CoordinateFilter> {
+        if let Some(f) = self.coordinate_filter.get() {
+            return Ok(f);
+        }
+        let filter = CoordinateFilter::from_args(&self.args)?;
+        Ok(self.coordinate_filter.get_or_init(|| filter))
+    }
+
+    /// the earliest metadata update to reify, from `--updated-since`
+    /// and `--updated-within-days`. If both are given, the later one
+    pub fn updated_cutoff(&self) -> Result<Option<DateTime<Utc>>> {
+        let since = match self.args.updated_since() {
+            Some(text) => match DateTime::parse_from_rfc3339(text) {
+                Ok(t) => Some(t.with_timezone(&Utc)),
+                Err(_) => match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+                    Ok(d) => d.and_hms_opt(0, 0, 0).map(|t| t.and_utc()),
+                    Err(e) => bail!("Bad --updated-since {:?}, {}", text, e),
+                },
+            },
+            None => None,
+        };
+        let within = self
+            .args
+            .updated_within_days()
+            .map(|days| Utc::now() - chrono::Duration::days(days));
+        Ok(since.max(within))
+    }
+
+    /// skip versions the artifact db already lists
+    pub fn only_new_versions(&self) -> bool {
+        self.args.only_new_versions()
+    }
+
+    /// the reify leaves out some packages or versions
+    pub fn is_scoped(&self) -> bool {
+        self.args.is_scoped()
+    }
+
+    /// how reify works out which files to fetch for each version
+    pub fn discovery(&self) -> ArtifactDiscovery {
+        self.args.discovery()
+    }
+
+    /// use crawls that never finished
+    pub fn allow_incomplete_crawl(&self) -> bool {
+        self.args.allow_incomplete_crawl()
+    }
+
+    /// all the crawl directories in the crawl db, oldest first
+    pub fn crawl_dirs(&self) -> Result<Vec<PathBuf>> {
+        let dir = self.args.crawl_db()?;
         if !dir.exists() || !dir.is_dir() {
             bail!("The crawl directory {:?} isn't a directory", dir);
         }
@@ -163,28 +524,207 @@ impl RunState {
             }
         }
         sub_files.sort();
+        Ok(sub_files)
+    }
+
+    /// get the directory that contains the latest crawl. Crawls
+    /// without a completion manifest are skipped unless
+    /// `--allow-incomplete-crawl` is set
+    pub fn latest_crawl(&self) -> Result<PathBuf> {
+        let mut sub_files = self.crawl_dirs()?;
+        if !self.allow_incomplete_crawl() {
+            sub_files.retain(|file_name| {
+                let complete = CrawlManifest::exists(file_name);
+                if !complete {
+                    info!("Skipping incomplete crawl {:?}", file_name);
+                }
+                complete
+            });
+        }
         match sub_files.last() {
             None => {
-                bail!("Could not find any entries in {:?}", dir);
+                bail!(
+                    "Could not find any completed crawls in {:?}, use `--allow-incomplete-crawl` to use an unfinished one",
+                    self.args.crawl_db()?
+                );
             }
             Some(pb) => Ok(pb.clone()),
         }
     }
 
+    /// download every metadata file rather than reusing copies
+    pub fn full_crawl(&self) -> bool {
+        self.args.full_crawl()
+    }
+
+    /// the latest completed crawl other than this one. Metadata
+    /// in it is reused if the server says it hasn't changed
+    pub fn previous_crawl(&self) -> Option<PathBuf> {
+        self.previous_crawl
+            .get_or_init(|| {
+                if self.full_crawl() {
+                    return None;
+                }
+                let current = self.crawl_db_dest_dir();
+                self.crawl_dirs()
+                    .ok()?
+                    .into_iter()
+                    .rfind(|d| d != &current && CrawlManifest::exists(d))
+            })
+            .clone()
+    }
+
+    /// the crawl that plan and reify work from: the one named
+    /// with `--crawl` or the latest one
+    pub fn crawl_to_merge(&self) -> Result<PathBuf> {
+        let name = match self.args.crawl() {
+            None => return self.latest_crawl(),
+            Some(name) => name,
+        };
+
+        let crawl_db = self.args.crawl_db()?;
+        let candidates = [
+            PathBuf::from(&name),
+            crawl_db.join(&name),
+            crawl_db.join(format!("{}_crawl_db", name)),
+        ];
+        let ret = match candidates.into_iter().find(|c| c.is_dir()) {
+            Some(dir) => dir,
+            None => {
+                if NaiveDateTime::parse_from_str(&name, "%Y_%m_%d_%H_%M_%S").is_err() {
+                    bail!(
+                        "`--crawl {}` is neither a crawl directory nor a date like 2025_04_13_14_43_04",
+                        name
+                    );
+                }
+                bail!("Could not find a crawl from {} in {:?}", name, crawl_db);
+            }
+        };
+
+        if !CrawlManifest::exists(&ret) {
+            warn!("The crawl {:?} did not finish", ret);
+        }
+        Ok(ret)
+    }
+
     pub fn assets_fetched(&self) -> usize {
         self.asset_fetch_cnt.load(Ordering::Relaxed)
     }
 
     pub fn push_page(&self, page: &str) {
-        self.total_added_pages.fetch_add(1, Ordering::Relaxed);
         let mut queue = self.queue.lock().expect("Lock queue");
-        queue.push_back(page.to_string())
+        if queue.visited.contains(page) {
+            return;
+        }
+        self.total_added_pages.fetch_add(1, Ordering::Relaxed);
+        queue.pending.push_back(page.to_string())
     }
 
+    /// get the next page to process. The page is tracked as
+    /// in progress until `finish_page` is called
     pub fn next_page(&self) -> Option<String> {
         let mut queue = self.queue.lock().expect("Lock queue");
-        queue.pop_front()
+        let ret = queue.pending.pop_front();
+        if let Some(page) = &ret {
+            queue.in_progress.insert(page.clone());
+        }
+        ret
+    }
+
+    /// the thread that got the page from `next_page` is done with it
+    pub fn finish_page(&self, page: &str) {
+        let mut queue = self.queue.lock().expect("Lock queue");
+        queue.in_progress.remove(page);
+    }
+
+    /// the page and all the pages below it have been crawled
+    pub fn mark_visited(&self, page: &str) {
+        let mut queue = self.queue.lock().expect("Lock queue");
+        if queue.visited.insert(page.to_string()) {
+            queue.newly_visited.push(page.to_string());
+        }
+    }
+
+    pub fn is_visited(&self, page: &str) -> bool {
+        let queue = self.queue.lock().expect("Lock queue");
+        queue.visited.contains(page)
+    }
+
+    /// snapshot the crawl so it can be resumed. Pages that
+    /// are in progress go back on the front of the queue
+    pub fn checkpoint(&self) -> Result<CrawlCheckpoint> {
+        let (pending, newly_visited) = {
+            let mut queue = self.queue.lock().expect("Lock queue");
+            let mut pending: Vec<String> = queue.in_progress.iter().cloned().collect();
+            pending.sort();
+            pending.extend(queue.pending.iter().cloned());
+            (pending, std::mem::take(&mut queue.newly_visited))
+        };
+        let visited = {
+            let mut checkpointed = self.checkpointed_visited.lock().expect("Lock visited");
+            checkpointed.extend(newly_visited);
+            checkpointed.clone()
+        };
+        Ok(CrawlCheckpoint {
+            repo_url: self.repo_url()?,
+            mirror_url: self.mirror_url().clone(),
+            saved_at: Utc::now(),
+            started_at: self.crawl_started_at(),
+            run_millis: self.run_duration().as_millis() as u64,
+            fetch_cnt: self.urls_fetched(),
+            asset_fetch_cnt: self.assets_fetched(),
+            total_added_pages: self.total_added_pages.load(Ordering::Relaxed),
+            total_bytes: self.get_total_bytes(),
+            metadata_cnt: self.metadata_cnt(),
+            not_modified_cnt: self.not_modified_cnt(),
+            checksum_skip_cnt: self.checksum_skip_cnt(),
+            error_cnt: self.error_cnt(),
+            queue: pending,
+            visited,
+        })
+    }
+
+    /// load the queue and counters from a checkpoint
+    pub fn restore_checkpoint(&self, checkpoint: CrawlCheckpoint) -> Result<()> {
+        let repo_url = self.repo_url()?;
+        if checkpoint.repo_url != repo_url {
+            bail!(
+                "Checkpoint is for repo {} but resuming with repo {}",
+                checkpoint.repo_url,
+                repo_url
+            );
+        }
+        self.fetch_cnt
+            .store(checkpoint.fetch_cnt, Ordering::Relaxed);
+        self.asset_fetch_cnt
+            .store(checkpoint.asset_fetch_cnt, Ordering::Relaxed);
+        self.total_added_pages
+            .store(checkpoint.total_added_pages, Ordering::Relaxed);
+        self.total_bytes
+            .store(checkpoint.total_bytes, Ordering::Relaxed);
+        self.prior_run_millis
+            .store(checkpoint.run_millis, Ordering::Relaxed);
+        self.metadata_cnt
+            .store(checkpoint.metadata_cnt, Ordering::Relaxed);
+        self.not_modified_cnt
+            .store(checkpoint.not_modified_cnt, Ordering::Relaxed);
+        self.checksum_skip_cnt
+            .store(checkpoint.checksum_skip_cnt, Ordering::Relaxed);
+        self.error_cnt
+            .store(checkpoint.error_cnt, Ordering::Relaxed);
+        *self.crawl_started_at.lock().expect("Lock start time") = checkpoint.started_at;
+
+        let mut queue = self.queue.lock().expect("Lock queue");
+        queue.visited = checkpoint.visited.iter().cloned().collect();
+        queue.newly_visited.clear();
+        *self.checkpointed_visited.lock().expect("Lock visited") = checkpoint.visited;
+        queue.pending = checkpoint.queue.into_iter().collect();
+        queue.in_progress.clear();
+        Ok(())
     }
 }
 
 pub type State = Arc<RunState>;
+
+/// the crawler's bookkeeping directory in the artifact db
+pub const ARTIFACT_DB_STATE_DIR: &str = ".mvn_crawler_carl";
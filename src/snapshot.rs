@@ -0,0 +1,132 @@
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use xmltree::Element as XmlElement;
+
+use crate::metadata::child_text;
+
+/// Maven's suffix for versions that are rebuilt in place
+pub const SNAPSHOT_SUFFIX: &str = "-SNAPSHOT";
+
+/// Which builds of a snapshot version to fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotBuilds {
+    /// the files of the latest build, from the version's `maven-metadata.xml`
+    #[default]
+    Latest,
+    /// every build in the version directory's listing
+    All,
+}
+
+pub fn is_snapshot(version: &str) -> bool {
+    version.ends_with(SNAPSHOT_SUFFIX)
+}
+
+/// the names of the latest build's files from a snapshot version's
+/// `maven-metadata.xml`. Uses `<snapshotVersions>` and, for older metadata
+/// that only has a `<snapshot>` timestamp and build number, the suffixes.
+/// Snapshots deployed without timestamps keep the `-SNAPSHOT` name
+pub fn snapshot_file_names(
+    metadata: &[u8],
+    artifact: &str,
+    version: &str,
+    suffixes: &[String],
+) -> Result<Vec<String>> {
+    let md = XmlElement::parse(metadata)?;
+    let versioning = match md.get_child("versioning") {
+        Some(v) => v,
+        None => bail!("No versioning in the metadata for {}-{}", artifact, version),
+    };
+
+    if let Some(snapshot_versions) = versioning.get_child("snapshotVersions") {
+        let ret: Vec<String> = snapshot_versions
+            .children
+            .iter()
+            .flat_map(|n| n.as_element())
+            .filter(|e| e.name == "snapshotVersion")
+            .flat_map(|e| {
+                let value = child_text(e, "value")?;
+                let extension = child_text(e, "extension")?;
+                Some(match child_text(e, "classifier") {
+                    Some(classifier) => {
+                        format!("{}-{}-{}.{}", artifact, value, classifier, extension)
+                    }
+                    None => format!("{}-{}.{}", artifact, value, extension),
+                })
+            })
+            .collect();
+        if !ret.is_empty() {
+            return Ok(ret);
+        }
+    }
+
+    let base = version.trim_end_matches("SNAPSHOT");
+    let build = versioning
+        .get_child("snapshot")
+        .and_then(|s| Some((child_text(s, "timestamp")?, child_text(s, "buildNumber")?)));
+    let value = match build {
+        Some((timestamp, build_number)) => format!("{}{}-{}", base, timestamp, build_number),
+        None => version.to_string(),
+    };
+    Ok(suffixes
+        .iter()
+        .map(|s| format!("{}-{}{}", artifact, value, s))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suffixes() -> Vec<String> {
+        vec![".jar".to_string(), ".pom".to_string()]
+    }
+
+    fn names(metadata: &str) -> Vec<String> {
+        snapshot_file_names(metadata.as_bytes(), "snap", "1.1-SNAPSHOT", &suffixes())
+            .expect("good metadata")
+    }
+
+    #[test]
+    fn snapshot_versions() {
+        let md = r#"<metadata><versioning><snapshotVersions>
+            <snapshotVersion><extension>jar</extension><value>1.1-20250101.120000-3</value></snapshotVersion>
+            <snapshotVersion><classifier>sources</classifier><extension>jar</extension><value>1.1-20250101.120000-3</value></snapshotVersion>
+            <snapshotVersion><extension>pom</extension><value> 1.1-20250101.120000-3 </value></snapshotVersion>
+            <snapshotVersion><extension>jar</extension></snapshotVersion>
+        </snapshotVersions></versioning></metadata>"#;
+        assert_eq!(
+            names(md),
+            vec![
+                "snap-1.1-20250101.120000-3.jar",
+                "snap-1.1-20250101.120000-3-sources.jar",
+                "snap-1.1-20250101.120000-3.pom",
+            ]
+        );
+    }
+
+    #[test]
+    fn older_snapshot_metadata() {
+        // only the timestamp and build number, so the suffixes are guessed
+        let md = r#"<metadata><versioning><snapshot>
+            <timestamp>20250101.120000</timestamp><buildNumber>3</buildNumber>
+        </snapshot></versioning></metadata>"#;
+        assert_eq!(
+            names(md),
+            vec![
+                "snap-1.1-20250101.120000-3.jar",
+                "snap-1.1-20250101.120000-3.pom",
+            ]
+        );
+
+        // deployed without timestamps
+        let md = "<metadata><versioning><snapshot><localCopy>true</localCopy></snapshot></versioning></metadata>";
+        assert_eq!(
+            names(md),
+            vec!["snap-1.1-SNAPSHOT.jar", "snap-1.1-SNAPSHOT.pom"]
+        );
+
+        assert!(snapshot_file_names(b"<metadata/>", "snap", "1.1-SNAPSHOT", &suffixes()).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    args::Args,
+    json_file::RecordedPolicy,
+    maven_version::{MavenVersion, VersionRange},
+};
+
+pub const VERSION_POLICY_FILE: &str = "version_policy.json";
+
+/// Which versions of each artifact reify fetches. By default, all of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionPolicy {
+    /// only the newest this many versions, in Maven's version order
+    #[serde(default)]
+    pub latest: Option<usize>,
+    /// skip snapshots and alpha, beta, milestone, and rc versions
+    #[serde(default)]
+    pub releases_only: bool,
+    /// only versions in the Maven version range, e.g., `[2.0,)`
+    #[serde(default)]
+    pub range: Option<String>,
+    #[serde(skip)]
+    parsed_range: Option<VersionRange>,
+}
+
+impl VersionPolicy {
+    pub fn from_args(args: &Args) -> Result<VersionPolicy> {
+        let parsed_range = match args.version_range() {
+            Some(spec) => Some(spec.parse::<VersionRange>()?),
+            None => None,
+        };
+        Ok(VersionPolicy {
+            latest: args.latest_versions(),
+            releases_only: args.releases_only(),
+            range: args.version_range().clone(),
+            parsed_range,
+        })
+    }
+
+    /// the versions to fetch, in the order they were listed
+    pub fn select(&self, versions: Vec<String>) -> Vec<String> {
+        if self.latest.is_none() && !self.releases_only && self.parsed_range.is_none() {
+            return versions;
+        }
+        let mut candidates: Vec<(usize, MavenVersion)> = versions
+            .iter()
+            .map(|v| MavenVersion::parse(v))
+            .enumerate()
+            .filter(|(_, v)| !(self.releases_only && v.is_pre_release()))
+            .filter(|(_, v)| self.parsed_range.as_ref().is_none_or(|r| r.contains(v)))
+            .collect();
+        if let Some(latest) = self.latest {
+            // newest first, then keep the metadata's order
+            candidates.sort_by(|a, b| b.1.cmp(&a.1));
+            candidates.truncate(latest);
+            candidates.sort_by_key(|(i, _)| *i);
+        }
+        candidates
+            .into_iter()
+            .map(|(_, v)| v.as_str().to_string())
+            .collect()
+    }
+
+    /// would the two policies pick the same versions?
+    pub fn same_versions_as(&self, other: &VersionPolicy) -> bool {
+        self.latest == other.latest
+            && self.releases_only == other.releases_only
+            && self.range == other.range
+    }
+}
+
+impl RecordedPolicy for VersionPolicy {
+    const FILE_NAME: &'static str = VERSION_POLICY_FILE;
+}